@@ -2,7 +2,7 @@ use bevy::{math::vec3, prelude::*};
 
 use crate::{Ray, components::*};
 
-use super::{fastest_linear_speed, find_support_point, Collidable};
+use super::{fastest_linear_speed, find_support_point, Collidable, RayIntersection};
 
 #[derive(Debug)]
 pub struct Box {
@@ -58,6 +58,10 @@ impl Collidable for Box {
         return Mat3::from_diagonal(diagonal);
     }
 
+    fn get_volume(&self) -> f32 {
+        self.size.x * self.size.y * self.size.z
+    }
+
     fn get_aabb(&self) -> Aabb {
         self.aabb
     }
@@ -66,7 +70,10 @@ impl Collidable for Box {
         aabb.clear();
         
         let margin = factor * velocity.linear.length();
-        let half_extends = self.half_size + Vec3::splat( margin);
+        // Extents of the rotated box along each world axis
+        let rot = Mat3::from_quat(trans.rotation);
+        let abs_rot = Mat3::from_cols(rot.x_axis.abs(), rot.y_axis.abs(), rot.z_axis.abs());
+        let half_extends = abs_rot * self.half_size + Vec3::splat( margin);
          aabb.mins = trans.translation - half_extends;
          aabb.maxs = trans.translation + half_extends;
         // for pt in &self.verts {
@@ -96,10 +103,36 @@ impl Collidable for Box {
     fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32 {
         fastest_linear_speed(&self.verts, angular_velocity, self.center_of_mass, dir)
     }
-    // Returns distance at which ray would hit the sphere, or None if it doesn't hit
-    fn intersect(&self, ray: &mut Ray) -> Option<f32> {
+    fn contains_point(&self, point: Vec3) -> bool {
+        point.abs().cmple(self.half_size).all()
+    }
+
+    fn project_point(&self, point: Vec3) -> Vec3 {
+        if !self.contains_point(point) {
+            return point.clamp(-self.half_size, self.half_size);
+        }
+
+        // Inside, push out through the nearest face
+        let dist_to_face = self.half_size - point.abs();
+        let mut projected = point;
+        let axis = if dist_to_face.x <= dist_to_face.y && dist_to_face.x <= dist_to_face.z {
+            0
+        } else if dist_to_face.y <= dist_to_face.z {
+            1
+        } else {
+            2
+        };
+        projected[axis] = self.half_size[axis] * if point[axis] < 0.0 { -1.0 } else { 1.0 };
+        projected
+    }
+
+    // Returns the distance, outward normal and face where the ray enters the box, or None if it misses
+    fn intersect(&self, ray: &mut Ray) -> Option<RayIntersection> {
+        // Track which axis each bound came from so we know the face that was hit
         let mut tmin = (self.aabb.mins.x - ray.origin.x) / ray.direction.x;
         let mut tmax = (self.aabb.maxs.x - ray.origin.x) / ray.direction.x;
+        let mut min_axis = 0;
+        let mut max_axis = 0;
 
         if tmin > tmax {
             std::mem::swap(&mut tmin, &mut tmax);
@@ -118,10 +151,12 @@ impl Collidable for Box {
 
         if tymin > tmin {
             tmin = tymin;
+            min_axis = 1;
         }
 
         if tymax < tmax {
             tmax = tymax;
+            max_axis = 1;
         }
 
         let mut tzmin = (self.aabb.mins.z - ray.origin.z) / ray.direction.z;
@@ -137,20 +172,71 @@ impl Collidable for Box {
 
         if tzmin > tmin {
             tmin = tzmin;
+            min_axis = 2;
         }
 
         if tzmax < tmax {
             tmax = tzmax;
+            max_axis = 2;
         }
 
         if tmax < 0.0 {
             return None;
         }
 
-        if tmin < 0.0 {
-            return Some(tmax);
-        }
+        // Entering faces point against the ray, when starting inside we hit the exit face instead
+        let (toi, axis, sign) = if tmin < 0.0 {
+            (tmax, max_axis, ray.direction[max_axis].signum())
+        } else {
+            (tmin, min_axis, -ray.direction[min_axis].signum())
+        };
+
+        let mut normal = Vec3::ZERO;
+        normal[axis] = sign;
+
+        Some(RayIntersection {
+            toi,
+            normal,
+            feature: axis as u32 * 2 + if sign > 0.0 { 1 } else { 0 },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_box_ray_face() {
+        let b = Box::new(Vec3::splat(2.0));
+        let mut ray = Ray {
+            origin: Vec3::new(0.0, 5.0, 0.0),
+            direction: -Vec3::Y,
+        };
+        let hit = b.intersect(&mut ray).unwrap();
+        assert_eq!(hit.toi, 4.0);
+        assert_eq!(hit.normal, Vec3::Y);
+        assert_eq!(hit.feature, 3);
+    }
+
+    #[test]
+    fn test_box_volume() {
+        let b = Box::new(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(b.get_volume(), 6.0);
+    }
 
-        Some(tmin)
+    #[test]
+    fn test_box_aabb_rotated() {
+        let b = Box::new(Vec3::new(2.0, 2.0, 2.0));
+        let mut aabb = Aabb::default();
+        let trans = Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4));
+        b.update_aabb(&mut aabb, &trans, &Velocity::default(), 0.0);
+
+        // The corners reach sqrt(2) along x and y once turned 45 degrees
+        let corner = 2.0f32.sqrt();
+        assert!((aabb.maxs.x - corner).abs() < 1e-5);
+        assert!((aabb.maxs.y - corner).abs() < 1e-5);
+        assert!((aabb.maxs.z - 1.0).abs() < 1e-5);
+        assert!((aabb.mins.x + corner).abs() < 1e-5);
     }
 }
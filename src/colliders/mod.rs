@@ -38,13 +38,31 @@ pub trait Collidable {
     
     // See https://en.wikipedia.org/wiki/List_of_moments_of_inertia
     fn get_inertia_tensor(&self, mass: f32) -> Mat3;
+    fn get_volume(&self) -> f32;
     fn get_aabb(&self) -> Aabb;
     fn update_aabb(&self, aabb: &mut Aabb, trans: &Transform, velocity: &Velocity, factor: f32);
     fn get_support(&self, trans: &Transform, dir: Vec3, bias: f32) -> Vec3;
     fn fastest_linear_speed(&self, angular_velocity: Vec3, dir: Vec3) -> f32;
 
     /// Note: Ray must already be converted to object space
-    fn intersect(&self, ray: &mut Ray) -> Option<f32>;
+    fn intersect(&self, ray: &mut Ray) -> Option<RayIntersection>;
+
+    /// Note: Point must already be converted to object space
+    fn contains_point(&self, point: Vec3) -> bool;
+
+    /// Closest point on the surface, in object space
+    fn project_point(&self, point: Vec3) -> Vec3;
+}
+
+/// Result of a ray test against a single collider, in object space
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayIntersection {
+    /// Distance along the ray, in units of the ray's direction
+    pub toi: f32,
+    /// Outward surface normal at the hit point
+    pub normal: Vec3,
+    /// Box face index in -x, +x, -y, +y, -z, +z order, always 0 for spheres
+    pub feature: u32,
 }
 
 /// Find the point in the furthest in direction
@@ -1,7 +1,7 @@
 use crate::{components::*};
 use bevy::{math::vec3, prelude::*};
 
-use super::Collidable;
+use super::{Collidable, RayIntersection};
 
 #[derive(Debug)]
 pub struct Sphere {
@@ -41,6 +41,10 @@ impl Collidable for Sphere {
         return Mat3::from_diagonal(Vec3::splat(i));        
     }
 
+    fn get_volume(&self) -> f32 {
+        (4.0 / 3.0) * std::f32::consts::PI * self.radius * self.radius * self.radius
+    }
+
     fn get_aabb(&self) -> Aabb {
         self.aabb
     }
@@ -78,8 +82,17 @@ impl Collidable for Sphere {
         0.0
     }
 
-    // Returns distance at which ray would hit the sphere, or None if it doesn't hit
-    fn intersect(&self, ray: &mut Ray) -> Option<f32> {
+    fn contains_point(&self, point: Vec3) -> bool {
+        point.length_squared() <= self.radius * self.radius
+    }
+
+    fn project_point(&self, point: Vec3) -> Vec3 {
+        let dir = point.try_normalize().unwrap_or(Vec3::Y);
+        dir * self.radius
+    }
+
+    // Returns the distance and outward normal where the ray enters the sphere, or None if it misses
+    fn intersect(&self, ray: &mut Ray) -> Option<RayIntersection> {
         let sphere_to_ray = ray.origin;
         let a = ray.direction.dot(ray.direction);
         let b = 2.0 * ray.direction.dot(ray.origin);
@@ -88,18 +101,38 @@ impl Collidable for Sphere {
         let discriminant = b * b - 4.0 * a * c;
         if discriminant < 0.0 {
             return None;
-        } else {
-            let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
-            let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
-
-            if t1 >= 0.0 && t2 >= 0.0 {
-                return Some(t1.min(t2));
-            } else if t1 >= 0.0 {
-                return Some(t1);
-            } else if t2 >= 0.0 {
-                return Some(t2);
-            }
         }
-        None
+
+        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
+        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
+
+        let toi = if t1 >= 0.0 && t2 >= 0.0 {
+            t1.min(t2)
+        } else if t1 >= 0.0 {
+            t1
+        } else if t2 >= 0.0 {
+            t2
+        } else {
+            return None;
+        };
+
+        let point = ray.origin + ray.direction * toi;
+        Some(RayIntersection {
+            toi,
+            normal: point.normalize_or_zero(),
+            feature: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sphere_volume() {
+        let s = Sphere::new(1.5);
+        let expected = 4.5 * std::f32::consts::PI;
+        assert!((s.get_volume() - expected).abs() < 1e-5);
     }
 }
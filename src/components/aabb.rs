@@ -132,4 +132,33 @@ impl Aabb {
     pub fn width(&self) -> Vec3 {
         self.maxs - self.mins
     }
+
+    /// Slab test, returns the distance along the ray where it enters the box, 0 if it starts inside
+    pub fn ray_intersection(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let inv_dir = direction.recip();
+        let t1 = (self.mins - origin) * inv_dir;
+        let t2 = (self.maxs - origin) * inv_dir;
+
+        let t_enter = t1.min(t2).max_element();
+        let t_exit = t1.max(t2).min_element();
+
+        if t_exit < 0.0 || t_enter > t_exit {
+            return None;
+        }
+        Some(t_enter.max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aabb_ray_intersection() {
+        let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        assert_eq!(aabb.ray_intersection(Vec3::new(-5.0, 0.0, 0.0), Vec3::X), Some(4.0));
+        assert_eq!(aabb.ray_intersection(Vec3::ZERO, Vec3::Y), Some(0.0));
+        assert_eq!(aabb.ray_intersection(Vec3::new(-5.0, 0.0, 0.0), -Vec3::X), None);
+        assert_eq!(aabb.ray_intersection(Vec3::new(-5.0, 3.0, 0.0), Vec3::X), None);
+    }
 }
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+
+/// World space force applied through every substep
+/// Non persistent forces are cleared at the end of the step
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct ExternalForce {
+    pub force: Vec3,
+    pub persistent: bool,
+}
+
+impl Default for ExternalForce {
+    fn default() -> Self {
+        Self::new(Vec3::ZERO)
+    }
+}
+
+impl ExternalForce {
+    pub fn new(force: Vec3) -> Self {
+        Self {
+            force,
+            persistent: true,
+        }
+    }
+
+    pub fn with_persistence(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
+    pub fn apply_force(&mut self, force: Vec3) -> &mut Self {
+        self.force += force;
+        self
+    }
+
+    /// Applies a force at a world space point, accumulating the torque it causes around the center of mass
+    pub fn apply_force_at_point(
+        &mut self,
+        torque: &mut ExternalTorque,
+        force: Vec3,
+        point: Vec3,
+        center_of_mass: Vec3,
+    ) -> &mut Self {
+        self.force += force;
+        torque.torque += (point - center_of_mass).cross(force);
+        self
+    }
+}
+
+/// World space torque applied through every substep
+/// Non persistent torques are cleared at the end of the step
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct ExternalTorque {
+    pub torque: Vec3,
+    pub persistent: bool,
+}
+
+impl Default for ExternalTorque {
+    fn default() -> Self {
+        Self::new(Vec3::ZERO)
+    }
+}
+
+impl ExternalTorque {
+    pub fn new(torque: Vec3) -> Self {
+        Self {
+            torque,
+            persistent: true,
+        }
+    }
+
+    pub fn with_persistence(mut self, persistent: bool) -> Self {
+        self.persistent = persistent;
+        self
+    }
+
+    pub fn apply_torque(&mut self, torque: Vec3) -> &mut Self {
+        self.torque += torque;
+        self
+    }
+}
+
+/// World space impulses applied once at the start of the next step, then cleared
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct ExternalImpulse {
+    pub impulse: Vec3,
+    pub angular_impulse: Vec3,
+}
+
+impl ExternalImpulse {
+    pub fn new(impulse: Vec3) -> Self {
+        Self {
+            impulse,
+            angular_impulse: Vec3::ZERO,
+        }
+    }
+
+    pub fn apply_impulse(&mut self, impulse: Vec3) -> &mut Self {
+        self.impulse += impulse;
+        self
+    }
+
+    pub fn apply_angular_impulse(&mut self, angular_impulse: Vec3) -> &mut Self {
+        self.angular_impulse += angular_impulse;
+        self
+    }
+
+    /// Applies an impulse at a world space point, the offset from the center of mass adds spin
+    pub fn apply_impulse_at_point(
+        &mut self,
+        impulse: Vec3,
+        point: Vec3,
+        center_of_mass: Vec3,
+    ) -> &mut Self {
+        self.impulse += impulse;
+        self.angular_impulse += (point - center_of_mass).cross(impulse);
+        self
+    }
+}
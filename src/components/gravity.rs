@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+/// Multiplies the gravity a body feels, 0 to float, negative to fall upwards
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct GravityScale(pub f32);
+
+impl Default for GravityScale {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Adds gravity around the entity it's on, on top of the global [`crate::Gravity`]
+/// Placed with the entity's GlobalTransform, so it can sit on a body or an empty entity
+/// Unlike the global gravity it isn't scaled by inverse mass, bodies of any mass fall alike
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub enum GravityField {
+    /// Pulls towards the center, falling off with distance squared, strength is G * M
+    Point { strength: f32, radius: f32 },
+    /// Constant acceleration inside a box, both in the entity's local space
+    Directional { acceleration: Vec3, half_extents: Vec3 },
+    /// Pulls towards the center with constant acceleration, like standing on a small planet
+    Radial { acceleration: f32, radius: f32 },
+}
+
+impl Default for GravityField {
+    fn default() -> Self {
+        GravityField::Radial {
+            acceleration: 9.81,
+            radius: 10.,
+        }
+    }
+}
+
+impl GravityField {
+    pub fn acceleration_at(&self, field_trans: &Transform, point: Vec3) -> Vec3 {
+        let to_center = field_trans.translation - point;
+        match self {
+            GravityField::Point { strength, radius } => {
+                let dist_sqr = to_center.length_squared();
+                if dist_sqr > radius * radius || dist_sqr < f32::EPSILON {
+                    return Vec3::ZERO;
+                }
+                to_center.normalize() * (*strength / dist_sqr)
+            }
+            GravityField::Directional {
+                acceleration,
+                half_extents,
+            } => {
+                let local_point = field_trans.rotation.inverse() * -to_center;
+                if local_point.abs().cmple(*half_extents).all() {
+                    field_trans.rotation * *acceleration
+                } else {
+                    Vec3::ZERO
+                }
+            }
+            GravityField::Radial {
+                acceleration,
+                radius,
+            } => {
+                if to_center.length_squared() > radius * radius {
+                    return Vec3::ZERO;
+                }
+                to_center.normalize_or_zero() * *acceleration
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_field() {
+        let field = GravityField::Point {
+            strength: 10.0,
+            radius: 5.0,
+        };
+        let trans = Transform::IDENTITY;
+        assert_eq!(field.acceleration_at(&trans, Vec3::new(2.0, 0.0, 0.0)), Vec3::new(-2.5, 0.0, 0.0));
+        assert_eq!(field.acceleration_at(&trans, Vec3::new(6.0, 0.0, 0.0)), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_directional_field_rotated() {
+        let field = GravityField::Directional {
+            acceleration: Vec3::new(0.0, -1.0, 0.0),
+            half_extents: Vec3::new(1.0, 2.0, 1.0),
+        };
+        // A quarter turn about z, so the box is long along x and pulls towards +x
+        let trans = Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+
+        let inside = field.acceleration_at(&trans, Vec3::new(1.5, 0.0, 0.0));
+        assert!(inside.abs_diff_eq(Vec3::X, 1e-5));
+        assert_eq!(field.acceleration_at(&trans, Vec3::new(0.0, 1.5, 0.0)), Vec3::ZERO);
+    }
+
+    #[test]
+    fn test_radial_field() {
+        let field = GravityField::default();
+        let trans = Transform::from_xyz(0.0, 1.0, 0.0);
+        assert_eq!(field.acceleration_at(&trans, Vec3::new(0.0, 4.0, 0.0)), Vec3::new(0.0, -9.81, 0.0));
+        assert_eq!(field.acceleration_at(&trans, Vec3::new(0.0, 12.0, 0.0)), Vec3::ZERO);
+        assert_eq!(field.acceleration_at(&trans, trans.translation), Vec3::ZERO);
+    }
+}
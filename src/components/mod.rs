@@ -1,6 +1,10 @@
 mod aabb;
+mod forces;
+mod gravity;
 pub use aabb::Aabb;
-use bevy::prelude::*;
+pub use forces::*;
+pub use gravity::*;
+use bevy::{prelude::*, utils::HashSet};
 
 use crate::{colliders::Collider};
 
@@ -22,6 +26,26 @@ impl Default for Mass {
     }
 }
 
+/// Mass per unit of volume, when present the body's [`Mass`] is derived from it and the collider's volume
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+pub struct Density(pub f32);
+
+impl Default for Density {
+    fn default() -> Self {
+        Self(1.)
+    }
+}
+
+/// Replaces mass properties that would otherwise be derived from the collider shape
+/// Both are in the body's local space, the inertia tensor is about the center of mass
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct MassPropertiesOverride {
+    pub center_of_mass: Option<Vec3>,
+    pub inertia_tensor: Option<Mat3>,
+}
+
 #[derive(Component,Reflect, Debug, Deref, DerefMut)]
 #[reflect(Component)]
 pub struct InverseMass(pub f32);
@@ -59,19 +83,89 @@ impl Default for Restitution {
     }
 }
 
+/// Fraction of linear velocity lost per second, overrides [`crate::PhysicsConfig::linear_damping`]
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct LinearDamping(pub f32);
+
+/// Fraction of angular velocity lost per second, overrides [`crate::PhysicsConfig::angular_damping`]
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct AngularDamping(pub f32);
+
+/// Optional dynamic friction coefficient, averaged between the two bodies in contact
+/// Bodies without one are treated as frictionless
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Friction(pub f32);
+
+/// Local space offset of the center of mass from the body's origin
+#[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct CenterOfMass(pub Vec3);
+
+impl CenterOfMass {
+    /// World space position of the center of mass for a body at trans
+    pub fn world(&self, trans: &Transform) -> Vec3 {
+        trans.translation + trans.rotation * self.0
+    }
+}
+
 #[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
 #[reflect(Component)]
 pub struct InertiaTensor(pub Mat3);
 
+impl InertiaTensor {
+    /// Rotated into world space, R · I · Rᵀ
+    pub fn rotated(&self, rotation: Quat) -> Mat3 {
+        let r = Mat3::from_quat(rotation);
+        r * self.0 * r.transpose()
+    }
+}
+
+/// Local space inverse inertia, see [`WorldInverseInertiaTensor`] for the one the solver uses
 #[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
 #[reflect(Component)]
 pub struct InverseInertiaTensor(pub Mat3);
 
+impl InverseInertiaTensor {
+    /// Rotated into world space, R · I⁻¹ · Rᵀ
+    pub fn rotated(&self, rotation: Quat) -> Mat3 {
+        let r = Mat3::from_quat(rotation);
+        r * self.0 * r.transpose()
+    }
+}
+
+/// Inverse inertia for the body's current orientation, refreshed every substep
+#[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct WorldInverseInertiaTensor(pub Mat3);
+
+impl WorldInverseInertiaTensor {
+    /// The stored tensor, or the local one rotated for bodies built without a WorldInverseInertiaTensor
+    pub fn get_or_rotate(world: Option<&Self>, local: &InverseInertiaTensor, rotation: Quat) -> Mat3 {
+        world.map_or_else(|| local.rotated(rotation), |w| w.0)
+    }
+}
+
 #[derive(Component, Reflect, Debug, PartialEq, Eq)]
 #[reflect(Component)]
 pub enum PhysicsMode {
     Dynamic,
     Static,
+    /// Moved by setting its Transform, velocity is derived from the change each step
+    KinematicPosition,
+    /// Moved by setting its Velocity, ignores gravity and forces
+    KinematicVelocity,
+}
+
+impl PhysicsMode {
+    pub fn is_kinematic(&self) -> bool {
+        matches!(
+            self,
+            PhysicsMode::KinematicPosition | PhysicsMode::KinematicVelocity
+        )
+    }
 }
 
 impl Default for PhysicsMode {
@@ -80,3 +174,226 @@ impl Default for PhysicsMode {
     }
 }
 
+
+/// Marks a body as a trigger volume, overlaps are reported through events and [`Collisions`]
+/// but never push bodies apart
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Sensor;
+
+/// Added by the engine to bodies at rest, sleeping bodies are not simulated and act as static
+/// in contacts until woken by a fast moving body or a change to their Transform or Velocity
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Sleeping;
+
+/// Added by the engine to bodies whose Transform or Velocity became non-finite, see
+/// [`crate::diagnostics::InstabilityDetected`]
+/// Quarantined bodies are frozen and left out of collisions until the user removes this or
+/// resets the body by changing its PhysicsMode
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Quarantined;
+
+/// Seconds the body has been below the sleep thresholds
+/// Optional, but an island with a body lacking one never falls asleep
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct SleepTimer(pub f32);
+
+/// Opt-in continuous collision detection for small fast bodies like bullets
+/// Each substep the body is swept from its previous position and stopped at the first impact
+#[derive(Component, Reflect, Debug, Default)]
+#[reflect(Component)]
+pub struct Ccd;
+
+/// Entities this body should never collide with, for example bodies connected by a joint
+/// Only one side of the pair needs to list the other
+#[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct CollisionExclusions(pub HashSet<Entity>);
+
+/// Layers the body is on and layers it collides with, one bit per layer
+/// Two bodies only collide when each is on a layer the other collides with, bodies without
+/// this component are on every layer and collide with every layer
+#[derive(Component, Reflect, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct CollisionLayers {
+    pub memberships: u32,
+    pub filters: u32,
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::new(Self::ALL, Self::ALL)
+    }
+}
+
+impl CollisionLayers {
+    pub const ALL: u32 = u32::MAX;
+
+    pub fn new(memberships: u32, filters: u32) -> Self {
+        Self {
+            memberships,
+            filters,
+        }
+    }
+
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.memberships & other.filters != 0 && other.memberships & self.filters != 0
+    }
+}
+
+/// A single contact reported in [`Collisions`]
+#[derive(Reflect, FromReflect, Debug, Copy, Clone)]
+pub struct CollisionData {
+    /// The entity being touched
+    pub entity: Entity,
+    /// Points from this body towards the other
+    pub normal: Vec3,
+    pub penetration: f32,
+    /// Normal impulse summed over the step's substeps, positive pushes the bodies apart
+    pub impulse: f32,
+}
+
+/// Opt-in list of everything touching this body, refilled every step
+#[derive(Component, Reflect, Debug, Default, Deref, DerefMut)]
+#[reflect(Component)]
+pub struct Collisions(pub Vec<CollisionData>);
+
+/// Degrees of freedom the solver may not move the body along, in world space
+/// Useful for keeping a body on a plane in 2.5D games or keeping characters upright
+#[derive(Component, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct LockedAxes(u8);
+
+impl LockedAxes {
+    const TRANSLATION_X: u8 = 1 << 0;
+    const TRANSLATION_Y: u8 = 1 << 1;
+    const TRANSLATION_Z: u8 = 1 << 2;
+    const ROTATION_X: u8 = 1 << 3;
+    const ROTATION_Y: u8 = 1 << 4;
+    const ROTATION_Z: u8 = 1 << 5;
+
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn lock_translation_x(self) -> Self {
+        Self(self.0 | Self::TRANSLATION_X)
+    }
+
+    pub fn lock_translation_y(self) -> Self {
+        Self(self.0 | Self::TRANSLATION_Y)
+    }
+
+    pub fn lock_translation_z(self) -> Self {
+        Self(self.0 | Self::TRANSLATION_Z)
+    }
+
+    pub fn lock_rotation_x(self) -> Self {
+        Self(self.0 | Self::ROTATION_X)
+    }
+
+    pub fn lock_rotation_y(self) -> Self {
+        Self(self.0 | Self::ROTATION_Y)
+    }
+
+    pub fn lock_rotation_z(self) -> Self {
+        Self(self.0 | Self::ROTATION_Z)
+    }
+
+    pub fn lock_translation(self) -> Self {
+        self.lock_translation_x().lock_translation_y().lock_translation_z()
+    }
+
+    pub fn lock_rotation(self) -> Self {
+        self.lock_rotation_x().lock_rotation_y().lock_rotation_z()
+    }
+
+    /// 1 for free axes and 0 for locked ones, multiply a linear quantity by it to drop locked parts
+    pub fn translation_mask(&self) -> Vec3 {
+        Vec3::new(
+            self.free(Self::TRANSLATION_X),
+            self.free(Self::TRANSLATION_Y),
+            self.free(Self::TRANSLATION_Z),
+        )
+    }
+
+    /// 1 for free axes and 0 for locked ones, multiply an angular quantity by it to drop locked parts
+    pub fn rotation_mask(&self) -> Vec3 {
+        Vec3::new(
+            self.free(Self::ROTATION_X),
+            self.free(Self::ROTATION_Y),
+            self.free(Self::ROTATION_Z),
+        )
+    }
+
+    /// Per axis inverse mass, locked axes act as infinitely heavy
+    pub fn inverse_mass(&self, inv_mass: f32) -> Vec3 {
+        self.translation_mask() * inv_mass
+    }
+
+    /// Inverse inertia with the locked rotation axes removed, so no torque can turn the body about them
+    pub fn inverse_inertia(&self, inv_inertia: Mat3) -> Mat3 {
+        let mask = Mat3::from_diagonal(self.rotation_mask());
+        mask * inv_inertia * mask
+    }
+
+    fn free(&self, axis: u8) -> f32 {
+        if self.0 & axis == 0 {
+            1.
+        } else {
+            0.
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_axes_masks() {
+        let locked = LockedAxes::new().lock_translation_y().lock_rotation_x().lock_rotation_z();
+        assert_eq!(locked.translation_mask(), Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(locked.rotation_mask(), Vec3::new(0.0, 1.0, 0.0));
+        assert_eq!(locked.inverse_mass(0.5), Vec3::new(0.5, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_locked_axes_inverse_inertia() {
+        let inv_inertia = Mat3::from_cols(
+            Vec3::new(1.0, 2.0, 3.0),
+            Vec3::new(2.0, 4.0, 5.0),
+            Vec3::new(3.0, 5.0, 6.0),
+        );
+        let locked = LockedAxes::new().lock_rotation_x().lock_rotation_y();
+
+        // Only the z row and column survive, so torque can only turn the body about z
+        let expected = Mat3::from_cols(Vec3::ZERO, Vec3::ZERO, Vec3::new(0.0, 0.0, 6.0));
+        assert_eq!(locked.inverse_inertia(inv_inertia), expected);
+        assert_eq!(LockedAxes::new().inverse_inertia(inv_inertia), inv_inertia);
+    }
+
+    #[test]
+    fn test_collision_layers() {
+        let player = CollisionLayers::new(0b01, 0b10);
+        let enemy = CollisionLayers::new(0b10, 0b01);
+        let ghost = CollisionLayers::new(0b100, 0);
+        assert!(player.interacts_with(&enemy));
+        assert!(!player.interacts_with(&player));
+        assert!(!ghost.interacts_with(&CollisionLayers::default()));
+        assert!(CollisionLayers::default().interacts_with(&enemy));
+    }
+
+    #[test]
+    fn test_inverse_inertia_rotated() {
+        let inv_inertia = InverseInertiaTensor(Mat3::from_diagonal(Vec3::new(1.0, 2.0, 3.0)));
+        let rotated = inv_inertia.rotated(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+
+        // A quarter turn about z swaps the x and y axes
+        let expected = Mat3::from_diagonal(Vec3::new(2.0, 1.0, 3.0));
+        assert!(rotated.abs_diff_eq(expected, 1e-5));
+    }
+}
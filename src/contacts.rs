@@ -1,9 +1,39 @@
 use bevy::prelude::*;
 
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct CollisionPair {
     pub entity_a: Entity,
     pub entity_b: Entity,
+    /// Set when the pair is separated but close enough to touch during the step
+    pub speculative: Option<SpeculativeContact>,
+}
+
+/// Closest points between a separated pair, recorded at the start of the step
+#[derive(Copy, Clone, Debug)]
+pub struct SpeculativeContact {
+    /// Points from a towards b
+    pub normal: Vec3,
+    pub local_point_a: Vec3,
+    pub local_point_b: Vec3,
+}
+
+impl SpeculativeContact {
+    /// Only reports an intersection once the bodies have actually closed the gap
+    pub fn intersection(&self, trans_a: &Transform, trans_b: &Transform) -> Option<Intersection> {
+        let point_a = trans_a.translation + trans_a.rotation * self.local_point_a;
+        let point_b = trans_b.translation + trans_b.rotation * self.local_point_b;
+        let separation = (point_b - point_a).dot(self.normal);
+        if separation < 0.0 {
+            Some(Intersection {
+                normal: self.normal,
+                penetration: -separation,
+                point_a,
+                point_b,
+            })
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -12,10 +42,22 @@ pub struct Contact {
     pub entity_b: Entity,
     pub normal: Vec3,
     pub penetration: f32,
+    /// Normal force implied by the position solve's multiplier, -λ / h², averaged over the
+    /// step's substeps once reported
+    pub normal_force: f32,
+    /// Normal impulse applied by the velocity solve, restitution included, summed over the step's
+    /// substeps once reported
+    /// Positive pushes the bodies apart, negative pulls them together
+    pub normal_impulse: f32,
+    /// Tangential impulse applied by the velocity solve, 0 unless a body has [`crate::prelude::Friction`]
+    pub friction_impulse: f32,
+    /// One of the bodies is a [`crate::prelude::Sensor`], no response is applied
+    pub sensor: bool,
+    /// Contact points relative to each body's origin, in the body's local space
+    pub local_point_a: Vec3,
+    pub local_point_b: Vec3,
     // pub world_point_a: Vec3,
     // pub world_point_b: Vec3,
-    // pub local_point_a: Vec3,
-    // pub local_point_b: Vec3,
     // pub separation_dist: f32,
     // pub time_of_impact: f32,
 }
@@ -23,4 +65,14 @@ pub struct Contact {
 pub struct Intersection {
     pub normal: Vec3,
     pub penetration: f32,    
-}
\ No newline at end of file
+    /// Deepest points of each body inside the other, in world space
+    pub point_a: Vec3,
+    pub point_b: Vec3,
+}
+/// Sent on the step two entities first touch
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CollisionStarted(pub Entity, pub Entity);
+
+/// Sent on the step two entities stop touching
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CollisionEnded(pub Entity, pub Entity);
@@ -0,0 +1,80 @@
+use bevy::prelude::*;
+use std::fmt;
+
+use crate::Step;
+
+/// Sent once when a body is found misconfigured, and again if it breaks after being fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PhysicsError {
+    pub entity: Entity,
+    pub kind: PhysicsErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhysicsErrorKind {
+    /// The collider asset isn't loaded yet or was dropped, the body is skipped until it's available
+    MissingCollider,
+    /// Has a collider but not everything in [`crate::PhysicsBundle`]
+    MissingComponents,
+    /// A dynamic body with a mass that isn't positive and finite
+    ZeroMass,
+    /// Translation, rotation or scale is NaN or infinite
+    NonFiniteTransform,
+}
+
+impl fmt::Display for PhysicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            PhysicsErrorKind::MissingCollider => "collider asset is not loaded, skipping body",
+            PhysicsErrorKind::MissingComponents => "is missing components from PhysicsBundle",
+            PhysicsErrorKind::ZeroMass => "dynamic body needs a positive, finite mass",
+            PhysicsErrorKind::NonFiniteTransform => "transform is not finite",
+        };
+        write!(f, "{:?} {}", self.entity, message)
+    }
+}
+
+pub(crate) fn transform_is_finite(trans: &Transform) -> bool {
+    trans.translation.is_finite() && trans.rotation.is_finite() && trans.scale.is_finite()
+}
+
+/// Sent when a body's Transform or Velocity stops being finite, with the values it had
+/// The body is moved back to where it started the substep, stopped and
+/// [`crate::components::Quarantined`] so the bad values can't spread through contacts
+#[derive(Debug, Clone)]
+pub struct InstabilityDetected {
+    pub entity: Entity,
+    /// The phase that produced the bad state
+    pub step: Step,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub linear_velocity: Vec3,
+    pub angular_velocity: Vec3,
+    /// Bodies it was paired with in the broad phase this step, likely involved
+    pub pairs: Vec<Entity>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_physics_error_display() {
+        let entity = Entity::from_raw(3);
+        let error = PhysicsError {
+            entity,
+            kind: PhysicsErrorKind::ZeroMass,
+        };
+        assert_eq!(
+            error.to_string(),
+            format!("{:?} dynamic body needs a positive, finite mass", entity)
+        );
+    }
+
+    #[test]
+    fn test_transform_is_finite() {
+        assert!(transform_is_finite(&Transform::from_xyz(1.0, 2.0, 3.0)));
+        assert!(!transform_is_finite(&Transform::from_xyz(f32::NAN, 0.0, 0.0)));
+        assert!(!transform_is_finite(&Transform::from_scale(Vec3::splat(f32::INFINITY))));
+    }
+}
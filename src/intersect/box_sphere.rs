@@ -59,8 +59,11 @@ pub fn box_sphere_intersect(
     // Compile the contact.
     let closest_point_world = box_trans.transform_point(closest_point);
 
+    let normal = (sphere_trans.translation - closest_point_world).normalize();
     Some(Intersection {
-        normal: (sphere_trans.translation - closest_point_world).normalize(),
+        normal,
         penetration: sphere_radius - dist.sqrt(),
+        point_a: closest_point_world,
+        point_b: sphere_trans.translation - normal * sphere_radius,
     })
 }
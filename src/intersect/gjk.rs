@@ -264,11 +264,12 @@ fn epa_expand(
     // (pt_on_a, pt_on_b)
     Some(Intersection {
         normal: delta.normalize_or_zero(),
-        penetration: delta.length()
+        penetration: delta.length(),
+        point_a: pt_on_a,
+        point_b: pt_on_b,
     })
 }
 
-#[allow(dead_code)]
 pub fn gjk_closest_points(
     collider_a: &Collider,
     trans_a: &Transform,
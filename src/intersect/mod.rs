@@ -1,8 +1,10 @@
 mod gjk;
 mod sphere;
 mod box_sphere;
+mod toi;
 
 pub (crate) use gjk::*;
 pub (crate) use sphere::*;
+pub (crate) use toi::*;
 #[allow(unused_imports)]
 pub (crate) use box_sphere::*;
\ No newline at end of file
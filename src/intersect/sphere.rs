@@ -17,6 +17,8 @@ pub fn sphere_sphere_intersect(
         Some(Intersection {
             normal,
             penetration,
+            point_a: pos_a + normal * radius_a,
+            point_b: pos_b - normal * radius_b,
         })
     } else {
         None
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::colliders::*;
+
+use super::{gjk_closest_points, gjk_intersect};
+
+// Separation at which conservative advancement considers the shapes touching
+const TOI_TOLERANCE: f32 = 0.001;
+const MAX_TOI_ITERATIONS: usize = 32;
+
+pub struct TimeOfImpact {
+    /// Distance travelled along the direction
+    pub toi: f32,
+    /// Contact point on the other collider
+    pub point: Vec3,
+    /// Surface normal of the other collider at the contact point
+    pub normal: Vec3,
+}
+
+/// Conservative advancement of collider_a along direction against a stationary collider_b,
+/// step forward by the closest distance over the closing speed until the shapes touch
+/// Direction must be normalized, rotation is held constant during the sweep
+pub fn time_of_impact(
+    collider_a: &Collider,
+    trans_a: &Transform,
+    direction: Vec3,
+    max_distance: f32,
+    collider_b: &Collider,
+    trans_b: &Transform,
+) -> Option<TimeOfImpact> {
+    // Already overlapping at the start
+    if let Some(intersection) = gjk_intersect(collider_a, trans_a, collider_b, trans_b, 0.0) {
+        return Some(TimeOfImpact {
+            toi: 0.0,
+            point: intersection.point_b,
+            normal: -intersection.normal,
+        });
+    }
+
+    let mut toi = 0.0;
+    let mut trans = *trans_a;
+    for _ in 0..MAX_TOI_ITERATIONS {
+        trans.translation = trans_a.translation + direction * toi;
+
+        let (pt_a, pt_b) = gjk_closest_points(collider_a, &trans, collider_b, trans_b);
+        let delta = pt_b - pt_a;
+        let dist = delta.length();
+        if dist < TOI_TOLERANCE {
+            return Some(TimeOfImpact {
+                toi,
+                point: pt_b,
+                normal: -delta.normalize_or_zero(),
+            });
+        }
+
+        // Moving away or parallel, will never touch
+        let closing_speed = direction.dot(delta / dist);
+        if closing_speed <= 0.0 {
+            return None;
+        }
+
+        toi += dist / closing_speed;
+        if toi > max_distance {
+            return None;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A sphere of radius 0.5 swept towards a box of size 2 at the origin
+    fn sweep(start: Vec3, direction: Vec3, max_distance: f32) -> Option<TimeOfImpact> {
+        time_of_impact(
+            &Collider::new_sphere(0.5),
+            &Transform::from_translation(start),
+            direction,
+            max_distance,
+            &Collider::new_box(2.0, 2.0, 2.0),
+            &Transform::IDENTITY,
+        )
+    }
+
+    #[test]
+    fn test_toi_hit() {
+        let hit = sweep(Vec3::new(-5.0, 0.0, 0.0), Vec3::X, 10.0).unwrap();
+        assert!((hit.toi - 3.5).abs() < 0.01);
+        assert!(hit.point.abs_diff_eq(Vec3::new(-1.0, 0.0, 0.0), 0.01));
+        assert!(hit.normal.abs_diff_eq(-Vec3::X, 1e-3));
+    }
+
+    #[test]
+    fn test_toi_miss() {
+        assert!(sweep(Vec3::new(-5.0, 0.0, 0.0), Vec3::Y, 10.0).is_none());
+        assert!(sweep(Vec3::new(-5.0, 0.0, 0.0), Vec3::X, 2.0).is_none());
+    }
+
+    #[test]
+    fn test_toi_overlapping() {
+        // The point is on the box, not on the sphere which reaches x = -0.9
+        let hit = sweep(Vec3::new(-1.4, 0.0, 0.0), Vec3::X, 10.0).unwrap();
+        assert_eq!(hit.toi, 0.0);
+        assert!((hit.point.x + 1.0).abs() < 0.01);
+    }
+}
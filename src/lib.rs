@@ -2,30 +2,46 @@ mod colliders;
 mod components;
 mod contacts;
 mod debug;
+mod diagnostics;
 mod intersect;
 mod math;
 mod phases;
+mod spatial_query;
 
-use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use bevy::{
+    ecs::schedule::ShouldRun,
+    prelude::*,
+    utils::HashMap,
+};
 use bevy_inspector_egui::prelude::*;
 use colliders::*;
 use components::*;
 use contacts::*;
+use diagnostics::*;
 use phases::*;
 use prelude::PrevPos;
 
 pub mod prelude {
     pub use crate::{
-        colliders::*, components::*, contacts::*, debug::*, PhysicsBundle, PhysicsPlugin,
+        colliders::*, components::*, contacts::*, debug::*, diagnostics::*, spatial_query::*, CollisionPairHooks,
+        PhysicsBundle, PhysicsPlugin, Step,
     };
 }
 
 pub struct PhysicsPlugin {
+    /// Applied to every dynamic body, scaled by its inverse mass
     pub gravity: Vec3,
     pub number_substeps: u32,
     pub number_position_iterations: u32,
     pub delta_time: f32,
     pub k: f32,
+    /// Lets resting islands fall asleep, off by default
+    pub sleeping: bool,
+    pub sleep_linear_threshold: f32,
+    pub sleep_angular_threshold: f32,
+    pub sleep_time: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
 }
 
 impl Default for PhysicsPlugin {
@@ -36,6 +52,12 @@ impl Default for PhysicsPlugin {
             number_position_iterations: 1,
             delta_time: 1. / 60.,
             k: 2.0,
+            sleeping: false,
+            sleep_linear_threshold: 0.15,
+            sleep_angular_threshold: 0.15,
+            sleep_time: 0.5,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
         }
     }
 }
@@ -50,12 +72,15 @@ pub struct PhysicsBundle {
 
     // Should not be set by user
     pub inverse_mass: InverseMass,
+    pub center_of_mass: CenterOfMass,
     pub inertia_tensor: InertiaTensor,
     pub inverse_inertia_tensor: InverseInertiaTensor,
+    pub world_inverse_inertia_tensor: WorldInverseInertiaTensor,
     pub aabb: Aabb,
     pub prev_pos: PrevPos,
     pub prev_rot: PrevRot,
     pub pre_solve_velocity: PreSolveVelocity,
+    pub sleep_timer: SleepTimer,
 }
 
 #[derive(Resource, InspectorOptions, Debug)]
@@ -67,6 +92,14 @@ pub struct PhysicsConfig {
     pub sub_delta_time: f32, // h in the paper
     #[inspector(min = 1.0)]
     pub k: f32,
+    /// When set, bodies slower than both thresholds for sleep_time seconds are put to sleep
+    pub sleeping: bool,
+    pub sleep_linear_threshold: f32,
+    pub sleep_angular_threshold: f32,
+    pub sleep_time: f32,
+    /// Used by bodies without a LinearDamping or AngularDamping component
+    pub linear_damping: f32,
+    pub angular_damping: f32,
 }
 
 #[derive(Resource, Debug, Default, Deref, DerefMut)]
@@ -75,10 +108,53 @@ pub struct SubstepContacts(pub Vec<Contact>);
 #[derive(Resource, Debug, Deref, Default, DerefMut)]
 pub struct CollisionPairs(pub Vec<contacts::CollisionPair>);
 
+/// Dynamic bodies connected through collision pairs, rebuilt each step
+/// Bodies in different islands never interact, the solver works through one island at a time
+/// Sleeping bodies stay in the island they fell asleep in
+#[derive(Debug, Default)]
+pub struct Island {
+    pub bodies: Vec<Entity>,
+    /// Indices into [`CollisionPairs`]
+    pub pairs: Vec<usize>,
+}
+
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct Islands(pub Vec<Island>);
+
+/// Entity pairs touching during any substep of the current and previous step
+#[derive(Resource, Debug, Default)]
+pub struct StepContacts {
+    pub previous: HashMap<(Entity, Entity), Contact>,
+    pub current: HashMap<(Entity, Entity), Contact>,
+}
+
+/// Global gravity, scaled by each dynamic body's inverse mass
 #[derive(Resource, Debug)]
 //#[reflect(Resource)]
 pub struct Gravity(pub Vec3);
 
+/// Every [`GravityField`] with its transform, gathered once per step
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct GravityFields(pub Vec<(Transform, GravityField)>);
+
+pub type CollisionPairHook = Box<dyn Fn(&mut CollisionPair) -> bool + Send + Sync>;
+
+/// Callbacks run on every broad phase pair before narrow phase
+/// Return false to drop the pair, the pair can also be modified in place
+#[derive(Resource, Default)]
+pub struct CollisionPairHooks(pub Vec<CollisionPairHook>);
+
+impl CollisionPairHooks {
+    pub fn add(&mut self, hook: impl Fn(&mut CollisionPair) -> bool + Send + Sync + 'static) {
+        self.0.push(Box::new(hook));
+    }
+
+    pub fn allows(&self, pair: &mut CollisionPair) -> bool {
+        // A hook could point both sides at the same entity, which can't collide with itself
+        self.0.iter().all(|hook| hook(pair)) && pair.entity_a != pair.entity_b
+    }
+}
+
 // This will be based on Algorith 2 (page 5) in https://github.com/matthias-research/pages/blob/master/publications/PBDBodies.pdf
 //  while simulating do
 //      CollectCollisionPairs();                                    broad phase
@@ -106,18 +182,40 @@ pub struct Gravity(pub Vec3);
 //      end
 //  end
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
-enum Step {
+/// Phases of a substep, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, SystemLabel)]
+pub enum Step {
     Setup,
     CollisionPairs,
     Integrate,
+    Ccd,
     SolvePositions,
     UpdateVelocities,
     SolveVelocities,
+    Sleeping,
+    Events,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
 struct FixedUpdateStage;
+impl PhysicsPlugin {
+    pub(crate) fn config(&self) -> PhysicsConfig {
+        PhysicsConfig {
+            number_substeps: self.number_substeps,
+            number_position_iterations: self.number_position_iterations,
+            delta_time: self.delta_time,
+            sub_delta_time: self.delta_time / self.number_substeps as f32,
+            k: self.k,
+            sleeping: self.sleeping,
+            sleep_linear_threshold: self.sleep_linear_threshold,
+            sleep_angular_threshold: self.sleep_angular_threshold,
+            sleep_time: self.sleep_time,
+            linear_damping: self.linear_damping,
+            angular_damping: self.angular_damping,
+        }
+    }
+}
+
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
         app
@@ -132,28 +230,51 @@ impl Plugin for PhysicsPlugin {
             .register_type::<InverseInertiaTensor>()
             .register_type::<Aabb>()
             .register_type::<Restitution>()
+            .register_type::<LinearDamping>()
+            .register_type::<AngularDamping>()
+            .register_type::<Friction>()
             .register_type::<Velocity>()
             .register_type::<PreSolveVelocity>()
             .register_type::<PrevPos>()
             .register_type::<PrevRot>()
+            .register_type::<Sensor>()
+            .register_type::<Sleeping>()
+            .register_type::<Quarantined>()
+            .register_type::<Ccd>()
+            .register_type::<ExternalForce>()
+            .register_type::<ExternalTorque>()
+            .register_type::<ExternalImpulse>()
+            .register_type::<GravityScale>()
+            .register_type::<GravityField>()
+            .register_type::<LockedAxes>()
+            .register_type::<Density>()
+            .register_type::<MassPropertiesOverride>()
+            .register_type::<CenterOfMass>()
+            .register_type::<WorldInverseInertiaTensor>()
+            .register_type::<SleepTimer>()
+            .register_type::<CollisionExclusions>()
+            .register_type::<CollisionLayers>()
+            .register_type::<Collisions>()
             // Add Asset
             .add_asset::<Collider>()
             // Add Resources
             .insert_resource(Gravity(self.gravity))
-            .insert_resource(PhysicsConfig {
-                number_substeps: self.number_substeps,
-                number_position_iterations: self.number_position_iterations,
-                delta_time: self.delta_time,
-                sub_delta_time: self.delta_time / self.number_substeps as f32,
-                k: self.k,
-            })
+            .insert_resource(self.config())
             .init_resource::<LoopState>()
             .init_resource::<SubstepContacts>()
             .init_resource::<CollisionPairs>()
+            .init_resource::<CollisionPairHooks>()
+            .init_resource::<Islands>()
+            .init_resource::<StepContacts>()
+            .init_resource::<GravityFields>()
             // Add Events
-            //.add_event::<CollisionPair>()
-            //.add_event::<Contact>()
+            .add_event::<CollisionStarted>()
+            .add_event::<CollisionEnded>()
+            .add_event::<Contact>()
+            .add_event::<PhysicsError>()
+            .add_event::<InstabilityDetected>()
             // Add Systems
+            .add_system_to_stage(CoreStage::PreUpdate, mark_modified_colliders)
             .add_stage_before(
                 CoreStage::Update,
                 FixedUpdateStage,
@@ -163,34 +284,91 @@ impl Plugin for PhysicsPlugin {
                         SystemSet::new()
                             .label(Step::Setup)
                             .with_run_criteria(first_substep)
+                            .with_system(validate_bodies)
                             .with_system(setup_prev_pos)
                             .with_system(setup_mass_and_inertia)
-                            .with_system(update_aabb),
+                            .with_system(apply_external_impulses.after(setup_mass_and_inertia))
+                            .with_system(update_kinematic_position.after(setup_prev_pos))
+                            .with_system(update_aabb.after(update_kinematic_position))
+                            .with_system(update_gravity_fields),
                     )
                     .with_system_set(
                         SystemSet::new()
                             .label(Step::CollisionPairs)
                             .after(Step::Setup)
                             .with_run_criteria(first_substep)
-                            .with_system(collision_pairs),
+                            .with_system(collision_pairs)
+                            .with_system(speculative_contacts.after(collision_pairs))
+                            .with_system(build_islands.after(speculative_contacts)),
+                    )
+                    .with_system(
+                        detect_instability(Step::Setup)
+                            .with_run_criteria(first_substep)
+                            .after(Step::Setup)
+                            .before(Step::CollisionPairs),
                     )
                     .with_system(integrate.label(Step::Integrate).after(Step::CollisionPairs))
+                    .with_system(
+                        detect_instability(Step::Integrate)
+                            .after(Step::Integrate)
+                            .before(Step::Ccd),
+                    )
+                    .with_system(ccd.label(Step::Ccd).after(Step::Integrate))
+                    .with_system(
+                        detect_instability(Step::Ccd)
+                            .after(Step::Ccd)
+                            .before(Step::SolvePositions),
+                    )
                     .with_system_set(
                         SystemSet::new()
                             .label(Step::SolvePositions)
-                            .after(Step::Integrate)
+                            .after(Step::Ccd)
                             .with_system(solve_pos),
                     )
+                    .with_system(
+                        detect_instability(Step::SolvePositions)
+                            .after(Step::SolvePositions)
+                            .before(Step::UpdateVelocities),
+                    )
                     .with_system(
                         update_vel
                             .label(Step::UpdateVelocities)
                             .after(Step::SolvePositions),
                     )
+                    .with_system(
+                        detect_instability(Step::UpdateVelocities)
+                            .after(Step::UpdateVelocities)
+                            .before(Step::SolveVelocities),
+                    )
                     .with_system_set(
                         SystemSet::new()
                             .label(Step::SolveVelocities)
                             .after(Step::UpdateVelocities)
-                            .with_system(solve_vel),
+                            .with_system(solve_vel)
+                            .with_system(collect_contacts.after(solve_vel)),
+                    )
+                    .with_system(
+                        detect_instability(Step::SolveVelocities)
+                            .after(Step::SolveVelocities)
+                            .before(Step::Sleeping),
+                    )
+                    .with_system_set(
+                        SystemSet::new()
+                            .label(Step::Sleeping)
+                            .after(Step::SolveVelocities)
+                            .with_run_criteria(last_substep)
+                            .with_system(update_sleeping)
+                            .with_system(clear_external_forces.after(update_sleeping))
+                            .with_system(store_kinematic_position),
+                    )
+                    .with_system_set(
+                        SystemSet::new()
+                            .label(Step::Events)
+                            .after(Step::Sleeping)
+                            .with_run_criteria(last_substep)
+                            .with_system(keep_resting_contacts.before(update_collisions))
+                            .with_system(update_collisions.before(send_collision_events))
+                            .with_system(send_collision_events),
                     ),
             );
     }
@@ -256,7 +434,6 @@ fn first_substep(state: Res<LoopState>) -> ShouldRun {
     }
 }
 
-#[allow(dead_code)]
 fn last_substep(state: Res<LoopState>, config: Res<PhysicsConfig>) -> ShouldRun {
     if state.current_substep == config.number_substeps - 1 {
         ShouldRun::Yes
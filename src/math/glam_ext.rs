@@ -1,4 +1,4 @@
-use bevy::math::{Mat3, Mat4};
+use bevy::math::{Mat3, Mat4, Quat, Vec3};
 
 pub trait Mat4Ext {
     fn minor(&self, i: usize, j: usize) -> Mat3;
@@ -34,3 +34,37 @@ impl Mat4Ext for Mat4 {
         i32::pow(-1, (i + 1 + j + 1) as u32) as f32 * minor.determinant()
     }
 }
+
+pub trait QuatExt {
+    /// Rotates by a small rotation vector, the linearized update from the XPBD paper
+    fn add_rotation(&self, rotation: Vec3) -> Quat;
+}
+
+impl QuatExt for Quat {
+    fn add_rotation(&self, rotation: Vec3) -> Quat {
+        let q = Quat::from_xyzw(rotation.x, rotation.y, rotation.z, 0.0) * *self;
+        Quat::from_xyzw(
+            self.x + 0.5 * q.x,
+            self.y + 0.5 * q.y,
+            self.z + 0.5 * q.z,
+            self.w + 0.5 * q.w,
+        )
+        .normalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_rotation() {
+        // Small rotations about each axis should match the exact quaternion, z included
+        let start = Quat::from_rotation_x(1.0);
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let rotated = start.add_rotation(axis * 0.001);
+            let expected = Quat::from_axis_angle(axis, 0.001) * start;
+            assert!(rotated.abs_diff_eq(expected, 1e-5));
+        }
+    }
+}
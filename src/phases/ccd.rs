@@ -0,0 +1,134 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{colliders::*, components::*, intersect::*, CollisionPairs};
+
+// How far past the time of impact to place the body, so the position solve sees a contact
+// and the velocity solve can apply restitution
+const CCD_PENETRATION: f32 = 0.005;
+
+// Sweep fast Ccd bodies from their previous position against their broad phase candidates,
+// and rewind them to the first time of impact so they can't tunnel through thin colliders
+pub fn ccd(
+    mut query: Query<(
+        &mut Transform,
+        &PrevPos,
+        &Handle<Collider>,
+        Option<&Ccd>,
+        Option<&Sensor>,
+    ), Without<Quarantined>>,
+    collision_pairs: Res<CollisionPairs>,
+    colliders: Res<Assets<Collider>>,
+) {
+    let mut impacts = HashMap::<Entity, f32>::new();
+    for pair in collision_pairs.iter() {
+        for (body, other) in [
+            (pair.entity_a, pair.entity_b),
+            (pair.entity_b, pair.entity_a),
+        ] {
+            let Ok((trans, prev_pos, collider_handle, Some(_), None)) = query.get(body) else {
+                continue;
+            };
+            let Ok((other_trans, _, other_collider_handle, _, None)) = query.get(other) else {
+                continue;
+            };
+            let (Some(collider), Some(other_collider)) = (
+                colliders.get(collider_handle),
+                colliders.get(other_collider_handle),
+            ) else {
+                continue;
+            };
+
+            // Slow enough for the discrete contacts to catch
+            let motion = trans.translation - prev_pos.0;
+            let dist = motion.length();
+            if dist < collider.get_aabb().width().min_element() * 0.5 {
+                continue;
+            }
+
+            let start = Transform {
+                translation: prev_pos.0,
+                ..*trans
+            };
+            if let Some(hit) = time_of_impact(
+                collider,
+                &start,
+                motion / dist,
+                dist,
+                other_collider,
+                other_trans,
+            ) {
+                // Already touching at the start, leave it to the position solve
+                if hit.toi > 0.0 {
+                    let toi = impacts.entry(body).or_insert(dist);
+                    *toi = toi.min(hit.toi);
+                }
+            }
+        }
+    }
+
+    for (entity, toi) in impacts {
+        let Ok((mut trans, prev_pos, ..)) = query.get_mut(entity) else {
+            continue;
+        };
+        let motion = trans.translation - prev_pos.0;
+        let dist = motion.length();
+        if toi < dist {
+            trans.translation = prev_pos.0 + motion / dist * (toi + CCD_PENETRATION).min(dist);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contacts::CollisionPair;
+    use bevy::asset::AssetPlugin;
+
+    #[test]
+    fn test_ccd_rewind() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Collider>()
+            .add_system(ccd);
+        let mut colliders = app.world.resource_mut::<Assets<Collider>>();
+        let sphere = colliders.add(Collider::new_sphere(0.1));
+        let wall = colliders.add(Collider::new_box(0.1, 2.0, 2.0));
+
+        let wall = app.world.spawn((Transform::IDENTITY, PrevPos::default(), wall)).id();
+        let mut spawn_sphere = |from: f32, to: f32| {
+            app.world
+                .spawn((
+                    Transform::from_xyz(to, 0.0, 0.0),
+                    PrevPos(Vec3::new(from, 0.0, 0.0)),
+                    sphere.clone(),
+                    Ccd,
+                ))
+                .id()
+        };
+        // Passes straight through the wall within a substep
+        let fast = spawn_sphere(-1.0, 1.0);
+        // Too slow for ccd, the discrete contacts catch it
+        let slow = spawn_sphere(-0.2, -0.12);
+        let quarantined = spawn_sphere(-1.0, 1.0);
+        app.world.entity_mut(quarantined).insert(Quarantined);
+
+        app.insert_resource(CollisionPairs(
+            [fast, slow, quarantined]
+                .map(|entity_a| CollisionPair {
+                    entity_a,
+                    entity_b: wall,
+                    speculative: None,
+                })
+                .into(),
+        ));
+        app.update();
+
+        // The sphere touches the wall's face at x = -0.05 after travelling 0.85
+        let x = |entity| app.world.get::<Transform>(entity).unwrap().translation.x;
+        assert!((x(fast) - (-1.0 + 0.85 + CCD_PENETRATION)).abs() < 0.002);
+        assert_eq!(x(slow), -0.12);
+        assert_eq!(x(quarantined), 1.0);
+        assert_eq!(x(wall), 0.0);
+    }
+}
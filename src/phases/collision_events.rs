@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::*, contacts::*, CollisionPairs, PhysicsConfig, StepContacts, SubstepContacts,
+};
+
+use super::is_awake;
+
+// SubstepContacts is cleared every substep, so keep a record of every pair touching during the step
+pub fn collect_contacts(
+    contacts: Res<SubstepContacts>,
+    mut step_contacts: ResMut<StepContacts>,
+    config: Res<PhysicsConfig>,
+) {
+    // Substeps without the contact count as zero force
+    let substeps = config.number_substeps as f32;
+    for c in contacts.iter() {
+        step_contacts
+            .current
+            .entry(pair_key(c.entity_a, c.entity_b))
+            .and_modify(|step_contact| {
+                // keep the latest contact, but sum the impulses and average the force over the step
+                let normal_force = step_contact.normal_force + c.normal_force / substeps;
+                let normal_impulse = step_contact.normal_impulse + c.normal_impulse;
+                let friction_impulse = step_contact.friction_impulse + c.friction_impulse;
+                *step_contact = *c;
+                step_contact.normal_force = normal_force;
+                step_contact.normal_impulse = normal_impulse;
+                step_contact.friction_impulse = friction_impulse;
+            })
+            .or_insert(Contact {
+                normal_force: c.normal_force / substeps,
+                ..*c
+            });
+    }
+}
+
+// Pairs of sleeping or static bodies aren't solved, but they are still touching, so keep the
+// contact from the step they fell asleep in, runs on the last substep
+pub fn keep_resting_contacts(
+    collision_pairs: Res<CollisionPairs>,
+    query: Query<(&PhysicsMode, Option<&Sleeping>)>,
+    mut step_contacts: ResMut<StepContacts>,
+) {
+    let StepContacts { previous, current } = &mut *step_contacts;
+    for pair in collision_pairs.iter() {
+        let (Ok((mode_a, sleeping_a)), Ok((mode_b, sleeping_b))) =
+            (query.get(pair.entity_a), query.get(pair.entity_b))
+        else {
+            continue;
+        };
+        if is_awake(mode_a, sleeping_a) || is_awake(mode_b, sleeping_b) {
+            continue;
+        }
+
+        let key = pair_key(pair.entity_a, pair.entity_b);
+        if let Some(contact) = previous.get(&key) {
+            current.entry(key).or_insert(*contact);
+        }
+    }
+}
+
+// Refill the opt-in Collisions components, runs on the last substep
+pub fn update_collisions(step_contacts: Res<StepContacts>, mut query: Query<&mut Collisions>) {
+    for mut collisions in query.iter_mut() {
+        collisions.clear();
+    }
+
+    for c in step_contacts.current.values() {
+        if let Ok(mut collisions) = query.get_mut(c.entity_a) {
+            collisions.push(CollisionData {
+                entity: c.entity_b,
+                normal: c.normal,
+                penetration: c.penetration,
+                impulse: c.normal_impulse,
+            });
+        }
+        if let Ok(mut collisions) = query.get_mut(c.entity_b) {
+            collisions.push(CollisionData {
+                entity: c.entity_a,
+                normal: -c.normal,
+                penetration: c.penetration,
+                impulse: c.normal_impulse,
+            });
+        }
+    }
+}
+
+// Diff this step's contacts against the last step's and report the step's contacts, runs on the last substep
+pub fn send_collision_events(
+    mut step_contacts: ResMut<StepContacts>,
+    mut started: EventWriter<CollisionStarted>,
+    mut ended: EventWriter<CollisionEnded>,
+    mut contact_events: EventWriter<Contact>,
+) {
+    let StepContacts { previous, current } = &mut *step_contacts;
+
+    contact_events.send_batch(current.values().copied());
+
+    for (a, b) in current.keys().filter(|key| !previous.contains_key(*key)) {
+        started.send(CollisionStarted(*a, *b));
+    }
+    for (a, b) in previous.keys().filter(|key| !current.contains_key(*key)) {
+        ended.send(CollisionEnded(*a, *b));
+    }
+
+    *previous = std::mem::take(current);
+}
+
+fn pair_key(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicsPlugin;
+
+    fn contact(entity_a: Entity, entity_b: Entity) -> Contact {
+        Contact {
+            entity_a,
+            entity_b,
+            normal: Vec3::X,
+            penetration: 0.01,
+            normal_force: 0.,
+            normal_impulse: 0.,
+            friction_impulse: 0.,
+            sensor: false,
+            local_point_a: Vec3::ZERO,
+            local_point_b: Vec3::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_collect_contacts() {
+        let mut world = World::new();
+        world.init_resource::<SubstepContacts>();
+        world.init_resource::<StepContacts>();
+        world.insert_resource(
+            PhysicsPlugin {
+                number_substeps: 2,
+                ..default()
+            }
+            .config(),
+        );
+        let (a, b) = (Entity::from_raw(0), Entity::from_raw(1));
+        let mut stage = SystemStage::single(collect_contacts);
+
+        world.resource_mut::<SubstepContacts>().0 = vec![Contact {
+            normal_force: 4.,
+            normal_impulse: 1.,
+            ..contact(a, b)
+        }];
+        stage.run(&mut world);
+        // Reported the other way around, a pulling correction counts against the push
+        world.resource_mut::<SubstepContacts>().0 = vec![Contact {
+            normal_force: 2.,
+            normal_impulse: -0.5,
+            friction_impulse: 0.25,
+            ..contact(b, a)
+        }];
+        stage.run(&mut world);
+
+        let step_contacts = world.resource::<StepContacts>();
+        assert_eq!(step_contacts.current.len(), 1);
+        let c = step_contacts.current[&(a, b)];
+        assert_eq!(c.normal_force, 3.);
+        assert_eq!(c.normal_impulse, 0.5);
+        assert_eq!(c.friction_impulse, 0.25);
+    }
+
+    #[test]
+    fn test_send_collision_events() {
+        let mut world = World::new();
+        world.init_resource::<StepContacts>();
+        world.init_resource::<Events<CollisionStarted>>();
+        world.init_resource::<Events<CollisionEnded>>();
+        world.init_resource::<Events<Contact>>();
+        let (a, b) = (Entity::from_raw(0), Entity::from_raw(1));
+        let mut stage = SystemStage::single(send_collision_events);
+
+        let mut step = |world: &mut World, touching: bool| {
+            if touching {
+                let mut step_contacts = world.resource_mut::<StepContacts>();
+                step_contacts.current.insert((a, b), contact(a, b));
+            }
+            stage.run(world);
+            let started: Vec<_> = world.resource_mut::<Events<CollisionStarted>>().drain().collect();
+            let ended: Vec<_> = world.resource_mut::<Events<CollisionEnded>>().drain().collect();
+            let contacts = world.resource_mut::<Events<Contact>>().drain().count();
+            (started, ended, contacts)
+        };
+
+        assert_eq!(step(&mut world, true), (vec![CollisionStarted(a, b)], vec![], 1));
+        // A steady pair only reports its contact
+        assert_eq!(step(&mut world, true), (vec![], vec![], 1));
+        assert_eq!(step(&mut world, false), (vec![], vec![CollisionEnded(a, b)], 0));
+        assert_eq!(step(&mut world, false), (vec![], vec![], 0));
+    }
+}
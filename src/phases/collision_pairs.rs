@@ -1,6 +1,15 @@
 use bevy::prelude::*;
 
-use crate::{components::*, contacts::*, colliders::*, CollisionPairs};
+use crate::{components::*, contacts::*, colliders::*, CollisionPairHooks, CollisionPairs};
+
+type BroadPhaseItem<'a> = (
+    Entity,
+    &'a Aabb,
+    &'a PhysicsMode,
+    Option<&'a CollisionExclusions>,
+    Option<&'a Sensor>,
+    Option<&'a CollisionLayers>,
+);
 
 // Sweep and Prune
 // The board phase is responsible for pruning the search space of possible collisions
@@ -8,7 +17,18 @@ use crate::{components::*, contacts::*, colliders::*, CollisionPairs};
 // So far this simple approach has been the fastest
 pub fn collision_pairs(
     mut collision_pairs: ResMut<CollisionPairs>,
-    query: Query<(Entity, &Aabb, &PhysicsMode), (With<Handle<Collider>>, With<InverseMass>)>,
+    hooks: Res<CollisionPairHooks>,
+    query: Query<
+        (
+            Entity,
+            &Aabb,
+            &PhysicsMode,
+            Option<&CollisionExclusions>,
+            Option<&Sensor>,
+            Option<&CollisionLayers>,
+        ),
+        (With<Handle<Collider>>, With<InverseMass>, Without<Quarantined>),
+    >,
 ) {
 
     collision_pairs.clear();
@@ -23,9 +43,9 @@ pub fn collision_pairs(
 
     //let t1 = Instant::now();
     // Sweep the array for collisions
-    for (i, (a, aabb_a, mode_a)) in list.iter().enumerate() {
+    for (i, (a, aabb_a, mode_a, exclusions_a, sensor_a, layers_a)) in list.iter().enumerate() {
         // Test collisions against all possible overlapping AABBs following current one
-        for (b, aabb_b, mode_b) in list.iter().skip(i + 1) {
+        for (b, aabb_b, mode_b, exclusions_b, sensor_b, layers_b) in list.iter().skip(i + 1) {
             // Stop when tested AABBs are beyond the end of current AABB            
             if aabb_b.mins.y > aabb_a.maxs.y {
                 break;
@@ -53,25 +73,61 @@ pub fn collision_pairs(
                 continue;
             }
 
-            match (mode_a, mode_b) {
-                (PhysicsMode::Static, PhysicsMode::Static) => {
-                    // Both are static, do nothing
-                }
-                (_, _) => {
-                    collision_pairs.push(CollisionPair {
-                        entity_a: *a,
-                        entity_b: *b,
-                    });
-                }
+            if !can_interact(mode_a, sensor_a.is_some(), mode_b, sensor_b.is_some()) {
+                continue;
+            }
+
+            let layers_a = layers_a.copied().unwrap_or_default();
+            if !layers_a.interacts_with(&layers_b.copied().unwrap_or_default()) {
+                continue;
+            }
+
+            if is_excluded(*exclusions_a, *b) || is_excluded(*exclusions_b, *a) {
+                continue;
+            }
+
+            let mut pair = CollisionPair {
+                entity_a: *a,
+                entity_b: *b,
+                speculative: None,
+            };
+            if hooks.allows(&mut pair) {
+                collision_pairs.push(pair);
             }
         }
     }
 }
 
+// Pairs where neither body responds are only kept for sensors, so a kinematic character can
+// still trigger a static sensor, the position solve skips sensor pairs either way
+fn can_interact(mode_a: &PhysicsMode, sensor_a: bool, mode_b: &PhysicsMode, sensor_b: bool) -> bool {
+    if *mode_a == PhysicsMode::Dynamic || *mode_b == PhysicsMode::Dynamic {
+        return true;
+    }
+    if *mode_a == PhysicsMode::Static && *mode_b == PhysicsMode::Static {
+        return false;
+    }
+    sensor_a || sensor_b
+}
+
+// Kinematic bodies are always awake so they can run into sleeping bodies
+// Pairs where neither body is awake are kept for islands and contact events, but not solved
+pub(crate) fn is_awake(mode: &PhysicsMode, sleeping: Option<&Sleeping>) -> bool {
+    match mode {
+        PhysicsMode::Dynamic => sleeping.is_none(),
+        PhysicsMode::Static => false,
+        PhysicsMode::KinematicPosition | PhysicsMode::KinematicVelocity => true,
+    }
+}
+
+fn is_excluded(exclusions: Option<&CollisionExclusions>, other: Entity) -> bool {
+    exclusions.map_or(false, |e| e.contains(&other))
+}
+
 #[allow(dead_code)]
 fn cmp_x_axis(
-    a: &(Entity, &Aabb, &PhysicsMode),
-    b: &(Entity, &Aabb, &PhysicsMode),
+    a: &BroadPhaseItem,
+    b: &BroadPhaseItem,
 ) -> std::cmp::Ordering {
     // Sort on minimum value along either x, y, or z axis
     let min_a = a.1.mins.x;
@@ -87,8 +143,8 @@ fn cmp_x_axis(
 
 #[allow(dead_code)]
 fn cmp_y_axis(
-    a: &(Entity, &Aabb, &PhysicsMode),
-    b: &(Entity, &Aabb, &PhysicsMode),
+    a: &BroadPhaseItem,
+    b: &BroadPhaseItem,
 ) -> std::cmp::Ordering {
     // Sort on minimum value along either x, y, or z axis
     let min_a = a.1.mins.y;
@@ -104,8 +160,8 @@ fn cmp_y_axis(
 
 #[allow(dead_code)]
 fn cmp_z_axis(
-    a: &(Entity, &Aabb, &PhysicsMode),
-    b: &(Entity, &Aabb, &PhysicsMode),
+    a: &BroadPhaseItem,
+    b: &BroadPhaseItem,
 ) -> std::cmp::Ordering {
     // Sort on minimum value along either x, y, or z axis
     let min_a = a.1.mins.z;
@@ -118,3 +174,84 @@ fn cmp_z_axis(
     }
     std::cmp::Ordering::Equal
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_interact() {
+        use PhysicsMode::*;
+        assert!(can_interact(&Dynamic, false, &Static, false));
+        assert!(!can_interact(&KinematicVelocity, false, &Static, false));
+        assert!(!can_interact(&KinematicPosition, false, &KinematicVelocity, false));
+        // Sensors still see kinematic bodies, but two static bodies never meet
+        assert!(can_interact(&KinematicPosition, false, &Static, true));
+        assert!(can_interact(&KinematicVelocity, true, &KinematicPosition, false));
+        assert!(!can_interact(&Static, true, &Static, false));
+    }
+
+    fn spawn_bodies(world: &mut World) -> [Entity; 3] {
+        [(); 3].map(|_| {
+            world
+                .spawn((
+                    Aabb::new(Vec3::ZERO, Vec3::ONE),
+                    PhysicsMode::Dynamic,
+                    Handle::<Collider>::default(),
+                    InverseMass(1.0),
+                ))
+                .id()
+        })
+    }
+
+    // Broad phase pairs with the lower entity first, in order
+    fn run(world: &mut World) -> Vec<(Entity, Entity)> {
+        world.init_resource::<CollisionPairs>();
+        world.init_resource::<CollisionPairHooks>();
+        SystemStage::single(collision_pairs).run(world);
+
+        let mut pairs: Vec<_> = world
+            .resource::<CollisionPairs>()
+            .iter()
+            .map(|pair| (pair.entity_a.min(pair.entity_b), pair.entity_a.max(pair.entity_b)))
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_exclusions_and_layers() {
+        let mut world = World::new();
+        let [a, b, c] = spawn_bodies(&mut world);
+        world
+            .entity_mut(a)
+            .insert(CollisionExclusions([b].into_iter().collect()));
+        assert_eq!(run(&mut world), vec![(a, c), (b, c)]);
+
+        world
+            .entity_mut(c)
+            .insert(CollisionLayers::new(0b10, CollisionLayers::ALL));
+        world
+            .entity_mut(b)
+            .insert(CollisionLayers::new(0b01, 0b01));
+        assert_eq!(run(&mut world), vec![(a, c)]);
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut world = World::new();
+        let [a, b, c] = spawn_bodies(&mut world);
+        world.init_resource::<CollisionPairHooks>();
+        world
+            .resource_mut::<CollisionPairHooks>()
+            .add(move |pair| pair.entity_a != c && pair.entity_b != c);
+        assert_eq!(run(&mut world), vec![(a, b)]);
+
+        // Pairs a hook turns into a body against itself are dropped
+        world.resource_mut::<CollisionPairHooks>().add(|pair| {
+            pair.entity_b = pair.entity_a;
+            true
+        });
+        assert!(run(&mut world).is_empty());
+    }
+}
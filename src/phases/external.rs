@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+
+use crate::components::*;
+
+// Impulses change velocity directly, once per step, runs on the first substep
+pub fn apply_external_impulses(
+    mut query: Query<(
+        &mut ExternalImpulse,
+        &mut Velocity,
+        &Transform,
+        &InverseMass,
+        &InverseInertiaTensor,
+        Option<&WorldInverseInertiaTensor>,
+        &PhysicsMode,
+        Option<&LockedAxes>,
+    )>,
+) {
+    for (mut impulse, mut vel, trans, inv_mass, inv_inertia_tensor, world_inv_inertia_tensor, mode, locked_axes) in
+        query.iter_mut()
+    {
+        if impulse.impulse == Vec3::ZERO && impulse.angular_impulse == Vec3::ZERO {
+            continue;
+        }
+
+        if mode == &PhysicsMode::Dynamic {
+            let locked_axes = locked_axes.copied().unwrap_or_default();
+            vel.linear += locked_axes.inverse_mass(inv_mass.0) * impulse.impulse;
+            let inv_inertia = WorldInverseInertiaTensor::get_or_rotate(
+                world_inv_inertia_tensor,
+                inv_inertia_tensor,
+                trans.rotation,
+            );
+            vel.angular += locked_axes.inverse_inertia(inv_inertia) * impulse.angular_impulse;
+        }
+
+        impulse.impulse = Vec3::ZERO;
+        impulse.angular_impulse = Vec3::ZERO;
+    }
+}
+
+// Non persistent forces only last a single step, runs on the last substep
+pub fn clear_external_forces(
+    mut forces: Query<&mut ExternalForce>,
+    mut torques: Query<&mut ExternalTorque>,
+) {
+    for mut force in forces.iter_mut() {
+        if !force.persistent && force.force != Vec3::ZERO {
+            force.force = Vec3::ZERO;
+        }
+    }
+    for mut torque in torques.iter_mut() {
+        if !torque.persistent && torque.torque != Vec3::ZERO {
+            torque.torque = Vec3::ZERO;
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::{components::*, diagnostics::*, CollisionPairs, Step};
+
+type InstabilityQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Velocity,
+        &'static mut PreSolveVelocity,
+        &'static mut PrevPos,
+        &'static mut PrevRot,
+    ),
+    (Without<Sleeping>, Without<Quarantined>),
+>;
+
+// Runs after every phase, so a NaN is caught before the next phase passes it on through contacts
+pub fn detect_instability(
+    step: Step,
+) -> impl FnMut(Commands, InstabilityQuery, Res<CollisionPairs>, EventWriter<InstabilityDetected>) {
+    move |mut commands, mut query, collision_pairs, mut events| {
+        for (entity, mut trans, mut vel, mut pre_solve_vel, mut prev_pos, mut prev_rot) in
+            query.iter_mut()
+        {
+            // Only deref mutably for broken bodies, so healthy ones aren't flagged as changed
+            if transform_is_finite(&trans) && vel.linear.is_finite() && vel.angular.is_finite() {
+                continue;
+            }
+
+            let event = InstabilityDetected {
+                entity,
+                step,
+                translation: trans.translation,
+                rotation: trans.rotation,
+                linear_velocity: vel.linear,
+                angular_velocity: vel.angular,
+                pairs: collision_pairs
+                    .iter()
+                    .filter_map(|pair| {
+                        if pair.entity_a == entity {
+                            Some(pair.entity_b)
+                        } else if pair.entity_b == entity {
+                            Some(pair.entity_a)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect(),
+            };
+            warn!(
+                "{:?} became non-finite during {:?}, freezing it: {:?}",
+                entity, step, event
+            );
+
+            // Back to the start of the substep, or the origin if that's broken too
+            if !prev_pos.0.is_finite() || !prev_rot.0.is_finite() {
+                prev_pos.0 = Vec3::ZERO;
+                prev_rot.0 = Quat::IDENTITY;
+            }
+            trans.translation = prev_pos.0;
+            trans.rotation = prev_rot.0;
+            if !trans.scale.is_finite() {
+                trans.scale = Vec3::ONE;
+            }
+            vel.linear = Vec3::ZERO;
+            vel.angular = Vec3::ZERO;
+            pre_solve_vel.linear = Vec3::ZERO;
+            pre_solve_vel.angular = Vec3::ZERO;
+
+            commands.entity(entity).insert(Quarantined);
+            events.send(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarantine() {
+        let mut world = World::new();
+        world.init_resource::<CollisionPairs>();
+        world.init_resource::<Events<InstabilityDetected>>();
+        let entity = world
+            .spawn((
+                Transform::from_xyz(f32::NAN, 0.0, 0.0),
+                Velocity::default(),
+                PreSolveVelocity::default(),
+                PrevPos(Vec3::ONE),
+                PrevRot::default(),
+            ))
+            .id();
+
+        let mut stage = SystemStage::single(detect_instability(Step::Integrate));
+        stage.run(&mut world);
+
+        // Moved back to where the substep started and frozen, not put to sleep
+        assert_eq!(world.get::<Transform>(entity).unwrap().translation, Vec3::ONE);
+        assert!(world.get::<Quarantined>(entity).is_some());
+        assert!(world.get::<Sleeping>(entity).is_none());
+        assert_eq!(world.resource::<Events<InstabilityDetected>>().len(), 1);
+
+        // Quarantined bodies are left alone until the user resets them
+        world.get_mut::<Transform>(entity).unwrap().translation.x = f32::NAN;
+        stage.run(&mut world);
+        assert!(world.get::<Transform>(entity).unwrap().translation.x.is_nan());
+        assert_eq!(world.resource::<Events<InstabilityDetected>>().len(), 1);
+    }
+}
@@ -1,6 +1,19 @@
-use crate::{colliders::*, components::*, Gravity, PhysicsConfig};
+use crate::{colliders::*, components::*, math::QuatExt, Gravity, GravityFields, PhysicsConfig};
 use bevy::prelude::*;
 
+// Decomposing a GlobalTransform isn't free, so do it once per field per step, runs on the first substep
+pub fn update_gravity_fields(
+    query: Query<(&GlobalTransform, &GravityField)>,
+    mut gravity_fields: ResMut<GravityFields>,
+) {
+    gravity_fields.clear();
+    gravity_fields.extend(
+        query
+            .iter()
+            .map(|(trans, field)| (trans.compute_transform(), *field)),
+    );
+}
+
 pub fn integrate(
     mut query: Query<(
         &mut Transform,
@@ -9,11 +22,18 @@ pub fn integrate(
         &mut Velocity,
 
         &InverseMass,
+        Option<&CenterOfMass>,
         &InertiaTensor,
         &InverseInertiaTensor,
+        Option<&mut WorldInverseInertiaTensor>,
         &PhysicsMode,
         &Handle<Collider>,
-    )>,
+        (Option<&ExternalForce>, Option<&ExternalTorque>),
+        (Option<&LinearDamping>, Option<&AngularDamping>),
+        Option<&GravityScale>,
+        Option<&LockedAxes>,
+    ), (Without<Sleeping>, Without<Quarantined>)>,
+    gravity_fields: Res<GravityFields>,
     gravity: Res<Gravity>,
     config: Res<PhysicsConfig>,
     colliders: Res<Assets<Collider>>,
@@ -24,13 +44,20 @@ pub fn integrate(
         mut prev_rot,
         mut vel,        
         inv_mass,
+        center_of_mass,
         inertia_tensor,
         inv_inertia_tensor,
+        mut world_inv_inertia_tensor,
         mode,
         collider_handle,
+        (external_force, external_torque),
+        (linear_damping, angular_damping),
+        gravity_scale,
+        locked_axes,
     ) in query.iter_mut()
     {
-        if mode == &PhysicsMode::Static {
+        // Static bodies don't move, and bodies without a loaded collider are skipped
+        if mode == &PhysicsMode::Static || !colliders.contains(collider_handle) {
             continue;
         }
 
@@ -39,27 +66,58 @@ pub fn integrate(
         prev_pos.0 = trans.translation;
         prev_rot.0 = trans.rotation;
 
-        let gravitation_force = inv_mass.0 * gravity.0;        
-        let external_force = gravitation_force;
-        let external_torque = Vec3::ZERO;
+        // Velocity is that of the center of mass, which may be offset from the origin
+        let center_of_mass = center_of_mass.map_or(Vec3::ZERO, |c| c.0);
+        let mut com = trans.translation + trans.rotation * center_of_mass;
 
-        vel.linear += config.sub_delta_time * external_force;
-        trans.translation += config.sub_delta_time * vel.linear;
+        // Global gravity is scaled by inverse mass like a force, fields are accelerations
+        let gravity = gravity_fields
+            .iter()
+            .fold(inv_mass.0 * gravity.0, |acc, (field_trans, field)| {
+                acc + field.acceleration_at(field_trans, com)
+            })
+            * gravity_scale.map_or(1.0, |s| s.0);
+        let external_force = external_force.map_or(Vec3::ZERO, |f| f.force);
+        let external_torque = external_torque.map_or(Vec3::ZERO, |t| t.torque);
+        let locked_axes = locked_axes.copied().unwrap_or_default();
+
+        // Kinematic bodies just follow their velocity
+        if !mode.is_kinematic() {
+            vel.linear += config.sub_delta_time * (gravity + inv_mass.0 * external_force);
+
+            let damping = linear_damping.map_or(config.linear_damping, |d| d.0);
+            vel.linear *= 1.0 / (1.0 + config.sub_delta_time * damping);
+            vel.linear *= locked_axes.translation_mask();
+        }
+        com += config.sub_delta_time * vel.linear;
         
 
         // rotation
-        let collider = colliders.get(collider_handle).unwrap();
-        
-        let change = config.sub_delta_time * inv_inertia_tensor.0 * (external_torque - vel.angular.cross( inertia_tensor.0 * vel.angular));        
-        vel.angular += change;
+        if !mode.is_kinematic() {
+            // Angular velocity is in world space, so the tensors have to be too
+            let inertia = inertia_tensor.rotated(trans.rotation);
+            let inv_inertia = WorldInverseInertiaTensor::get_or_rotate(
+                world_inv_inertia_tensor.as_deref(),
+                inv_inertia_tensor,
+                trans.rotation,
+            );
+            let inv_inertia = locked_axes.inverse_inertia(inv_inertia);
+            let change = config.sub_delta_time * inv_inertia * (external_torque - vel.angular.cross(inertia * vel.angular));
+            vel.angular += change;
+
+            let damping = angular_damping.map_or(config.angular_damping, |d| d.0);
+            vel.angular *= 1.0 / (1.0 + config.sub_delta_time * damping);
+            vel.angular *= locked_axes.rotation_mask();
+        }
 
-        // USE_QUATERNIONS_LINEARIZED_FORMULAS
-        let aux = Quat::from_xyzw( vel.angular.x, vel.angular.y, vel.angular.z, 0.0);
-        let q = aux * trans.rotation;
-        trans.rotation.x +=  config.sub_delta_time * 0.5 * q.x;
-        trans.rotation.y +=  config.sub_delta_time * 0.5 * q.y;
-        trans.rotation.z +=  config.sub_delta_time * 0.5 * q.y;
-        trans.rotation.w +=  config.sub_delta_time * 0.5 * q.w;
-        trans.rotation = trans.rotation.normalize();
+        trans.rotation = trans.rotation.add_rotation(config.sub_delta_time * vel.angular);
+
+        // Rotate around the center of mass rather than the origin
+        trans.translation = com - trans.rotation * center_of_mass;
+
+        // The solve phases see the orientation the body has now
+        if let Some(world_inv_inertia_tensor) = world_inv_inertia_tensor.as_mut() {
+            world_inv_inertia_tensor.0 = inv_inertia_tensor.rotated(trans.rotation);
+        }
     }
 }
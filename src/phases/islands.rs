@@ -0,0 +1,161 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{components::*, CollisionPairs, Island, Islands};
+
+// Union-find over the collision pairs, static bodies don't join islands so a shared ground
+// doesn't merge everything into one
+pub fn build_islands(
+    query: Query<(Entity, &PhysicsMode, Option<&Sleeping>), Without<Quarantined>>,
+    collision_pairs: Res<CollisionPairs>,
+    mut islands: ResMut<Islands>,
+) {
+    let previous = std::mem::take(&mut islands.0);
+
+    let bodies = query
+        .iter()
+        .filter(|(_, mode, _)| **mode == PhysicsMode::Dynamic)
+        .map(|(entity, ..)| entity)
+        .collect::<Vec<_>>();
+    let index = bodies
+        .iter()
+        .enumerate()
+        .map(|(i, entity)| (*entity, i))
+        .collect::<HashMap<_, _>>();
+
+    let mut sets = DisjointSet::new(bodies.len());
+    for pair in collision_pairs.iter() {
+        if let (Some(a), Some(b)) = (index.get(&pair.entity_a), index.get(&pair.entity_b)) {
+            sets.union(*a, *b);
+        }
+    }
+
+    // Sleeping bodies keep the island they fell asleep in, so a resting stack stays whole even
+    // when one of its pairs drops out of the broad phase
+    for island in previous.iter() {
+        let mut asleep = island
+            .bodies
+            .iter()
+            .filter(|entity| matches!(query.get(**entity), Ok((_, _, Some(_)))))
+            .filter_map(|entity| index.get(entity));
+        if let Some(first) = asleep.next() {
+            for other in asleep {
+                sets.union(*first, *other);
+            }
+        }
+    }
+
+    // Map each set's root to an island
+    let mut root_to_island = HashMap::new();
+    for (i, entity) in bodies.iter().enumerate() {
+        let root = sets.find(i);
+        let island = *root_to_island.entry(root).or_insert_with(|| {
+            islands.push(Island::default());
+            islands.len() - 1
+        });
+        islands[island].bodies.push(*entity);
+    }
+
+    for (pair_index, pair) in collision_pairs.iter().enumerate() {
+        let body = index
+            .get(&pair.entity_a)
+            .or_else(|| index.get(&pair.entity_b));
+        if let Some(body) = body {
+            let island = root_to_island[&sets.find(*body)];
+            islands[island].pairs.push(pair_index);
+        }
+    }
+}
+
+struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+        }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+
+        // Path compression
+        let mut i = i;
+        while self.parent[i] != root {
+            let next = self.parent[i];
+            self.parent[i] = root;
+            i = next;
+        }
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return;
+        }
+
+        match self.rank[a].cmp(&self.rank[b]) {
+            std::cmp::Ordering::Less => self.parent[a] = b,
+            std::cmp::Ordering::Greater => self.parent[b] = a,
+            std::cmp::Ordering::Equal => {
+                self.parent[b] = a;
+                self.rank[a] += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disjoint_set() {
+        let mut sets = DisjointSet::new(5);
+        sets.union(0, 1);
+        sets.union(3, 4);
+        sets.union(1, 4);
+        assert_eq!(sets.find(0), sets.find(3));
+        assert_ne!(sets.find(0), sets.find(2));
+    }
+
+    #[test]
+    fn test_sleeping_bodies_keep_their_island() {
+        let mut world = World::new();
+        world.init_resource::<CollisionPairs>();
+        let asleep = [
+            world.spawn((PhysicsMode::Dynamic, Sleeping)).id(),
+            world.spawn((PhysicsMode::Dynamic, Sleeping)).id(),
+        ];
+        let awake = [
+            world.spawn(PhysicsMode::Dynamic).id(),
+            world.spawn(PhysicsMode::Dynamic).id(),
+        ];
+        world.insert_resource(Islands(
+            [asleep, awake]
+                .map(|bodies| Island {
+                    bodies: bodies.to_vec(),
+                    pairs: Vec::new(),
+                })
+                .into(),
+        ));
+
+        // No pairs this step, only the sleeping bodies stay together
+        let mut stage = SystemStage::single(build_islands);
+        stage.run(&mut world);
+
+        let islands = world.resource::<Islands>();
+        assert_eq!(islands.len(), 3);
+        assert!(islands.iter().any(|island| {
+            island.bodies.len() == 2 && asleep.iter().all(|e| island.bodies.contains(e))
+        }));
+    }
+}
@@ -0,0 +1,55 @@
+use bevy::prelude::*;
+
+use crate::{components::*, PhysicsConfig};
+
+// Turn the user's change to a kinematic body's Transform into a velocity and rewind it, so the
+// substeps carry it to the target and contacts see how fast it moved, runs on the first substep
+pub fn update_kinematic_position(
+    mut query: Query<(
+        &mut Transform,
+        &PrevPos,
+        &PrevRot,
+        &mut Velocity,
+        Option<&CenterOfMass>,
+        &PhysicsMode,
+    )>,
+    config: Res<PhysicsConfig>,
+) {
+    for (mut trans, prev_pos, prev_rot, mut vel, center_of_mass, mode) in query.iter_mut() {
+        if mode != &PhysicsMode::KinematicPosition {
+            continue;
+        }
+
+        if trans.translation == prev_pos.0 && trans.rotation == prev_rot.0 {
+            if vel.linear != Vec3::ZERO || vel.angular != Vec3::ZERO {
+                vel.linear = Vec3::ZERO;
+                vel.angular = Vec3::ZERO;
+            }
+            continue;
+        }
+
+        let center_of_mass = center_of_mass.map_or(Vec3::ZERO, |c| c.0);
+        let prev_com = prev_pos.0 + prev_rot.0 * center_of_mass;
+        let com = trans.translation + trans.rotation * center_of_mass;
+        vel.linear = (com - prev_com) / config.delta_time;
+
+        let delta_q = trans.rotation * prev_rot.0.inverse();
+        let sign = if delta_q.w >= 0.0 { 1.0 } else { -1.0 };
+        vel.angular = (sign * 2.0 / config.delta_time) * Vec3::new(delta_q.x, delta_q.y, delta_q.z);
+
+        trans.translation = prev_pos.0;
+        trans.rotation = prev_rot.0;
+    }
+}
+
+// Remember where kinematic position bodies ended the step, runs on the last substep
+pub fn store_kinematic_position(
+    mut query: Query<(&Transform, &mut PrevPos, &mut PrevRot, &PhysicsMode)>,
+) {
+    for (trans, mut prev_pos, mut prev_rot, mode) in query.iter_mut() {
+        if mode == &PhysicsMode::KinematicPosition {
+            prev_pos.0 = trans.translation;
+            prev_rot.0 = trans.rotation;
+        }
+    }
+}
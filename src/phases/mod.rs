@@ -4,10 +4,28 @@ mod solve_positions;
 mod solve_velocities;
 mod update_velocities;
 mod integrate;
+mod collision_events;
+mod sleeping;
+mod islands;
+mod ccd;
+mod speculative;
+mod external;
+mod kinematic;
+mod validate;
+mod instability;
 
 pub(crate) use setup::*;
 pub(crate) use collision_pairs::*;
 pub(crate) use solve_positions::*;
 pub(crate) use solve_velocities::*;
 pub(crate) use update_velocities::*;
-pub(crate) use integrate::*;
\ No newline at end of file
+pub(crate) use integrate::*;
+pub(crate) use collision_events::*;
+pub(crate) use sleeping::*;
+pub(crate) use islands::*;
+pub(crate) use ccd::*;
+pub(crate) use speculative::*;
+pub(crate) use external::*;
+pub(crate) use kinematic::*;
+pub(crate) use validate::*;
+pub(crate) use instability::*;
\ No newline at end of file
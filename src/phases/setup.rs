@@ -1,60 +1,181 @@
-use bevy::prelude::*;
+use bevy::{asset::HandleId, prelude::*, utils::HashSet};
 
 use crate::{colliders::*, components::*, PhysicsConfig};
 
 pub fn setup_prev_pos(
-    mut query: Query<(&Transform, &mut Velocity, &mut PrevPos, &PhysicsMode), Added<Velocity>>,
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Velocity,
+            &mut PreSolveVelocity,
+            &mut PrevPos,
+            &mut PrevRot,
+            &PhysicsMode,
+            Option<&Quarantined>,
+        ),
+        Or<(Added<Velocity>, Changed<PhysicsMode>)>,
+    >,
     config: Res<PhysicsConfig>,
 ) {
-    for (trans, mut vel, mut prev_pos, mode) in query.iter_mut() {
-        // clear any velocity on static objects
+    for (entity, trans, mut vel, mut pre_solve_vel, mut prev_pos, mut prev_rot, mode, quarantined) in
+        query.iter_mut()
+    {
+        // The body was reset, give it another chance
+        if quarantined.is_some() {
+            commands.entity(entity).remove::<Quarantined>();
+        }
+
+        // clear any velocity on static objects, the velocity solve still reads it through contacts
         match mode {
             PhysicsMode::Static => {
                 vel.linear = Vec3::ZERO;
+                vel.angular = Vec3::ZERO;
+                pre_solve_vel.linear = Vec3::ZERO;
+                pre_solve_vel.angular = Vec3::ZERO;
+            }
+            PhysicsMode::KinematicPosition => {
+                vel.linear = Vec3::ZERO;
             }
             _ => {}
         }
         prev_pos.0 = trans.translation - vel.linear * config.sub_delta_time;
+        prev_rot.0 = trans.rotation;
+    }
+}
+
+// Flag bodies whose collider asset was (re)loaded or edited, so the systems below that watch
+// for a changed Handle<Collider> recompute mass properties and bounds
+// Runs every frame in PreUpdate, the fixed step may skip frames and miss the asset events
+pub fn mark_modified_colliders(
+    mut events: EventReader<AssetEvent<Collider>>,
+    mut query: Query<&mut Handle<Collider>>,
+) {
+    let modified: HashSet<HandleId> = events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => Some(handle.id()),
+            AssetEvent::Removed { .. } => None,
+        })
+        .collect();
+    if modified.is_empty() {
+        return;
+    }
+
+    for mut handle in query.iter_mut() {
+        if modified.contains(&handle.id()) {
+            handle.set_changed();
+        }
     }
 }
 
 pub fn setup_mass_and_inertia(
+    mut commands: Commands,
     mut query: Query<
         (
+            Entity,
             &mut Mass,
             &mut InverseMass,
+            Option<&mut CenterOfMass>,
             &mut InertiaTensor,
             &mut InverseInertiaTensor,
+            Option<&mut WorldInverseInertiaTensor>,
+            &Transform,
             &Handle<Collider>,
             &PhysicsMode,
+            Option<&Density>,
+            Option<&MassPropertiesOverride>,
+            Option<&Sleeping>,
         ),
-        Changed<Mass>,
+        Or<(
+            Changed<Mass>,
+            Changed<Density>,
+            Changed<MassPropertiesOverride>,
+            Changed<PhysicsMode>,
+            Changed<Handle<Collider>>,
+        )>,
     >,
     colliders: Res<Assets<Collider>>,
 ) {
     // setup inverse mass and mass
-    for (mut mass, mut inv_mass, mut inertia_tensor, mut inv_inertia_tensor, collider_handle, option) in
-        query.iter_mut()
+    for (
+        entity,
+        mut mass,
+        mut inv_mass,
+        center_of_mass,
+        mut inertia_tensor,
+        mut inv_inertia_tensor,
+        world_inv_inertia_tensor,
+        trans,
+        collider_handle,
+        option,
+        density,
+        mass_override,
+        sleeping,
+    ) in query.iter_mut()
     {
-        let collider = colliders.get(collider_handle).unwrap();
+        // Recomputed by mark_modified_colliders once the asset loads
+        let Some(collider) = colliders.get(collider_handle) else {
+            continue;
+        };
+
+        // Density takes priority over the mass set on the body
+        if let Some(density) = density {
+            let density_mass = density.0 * collider.get_volume();
+            if mass.0 != density_mass && option != &PhysicsMode::Static {
+                mass.0 = density_mass;
+            }
+        }
+
+        // Reported by validate_bodies, leave the previous values rather than divide by zero
+        if option == &PhysicsMode::Dynamic && !(mass.0 > 0. && mass.0.is_finite()) {
+            continue;
+        }
+
+        if let Some(mut center_of_mass) = center_of_mass {
+            center_of_mass.0 = mass_override
+                .and_then(|o| o.center_of_mass)
+                .unwrap_or_else(|| collider.get_center_of_mass());
+        }
+        let inertia = mass_override
+            .and_then(|o| o.inertia_tensor)
+            .unwrap_or_else(|| collider.get_inertia_tensor(mass.0));
+
         match option {
             PhysicsMode::Dynamic => {
                 inv_mass.0 = 1. / mass.0;
-                inertia_tensor.0 = collider.get_inertia_tensor(mass.0);
+                inertia_tensor.0 = inertia;
+                inv_inertia_tensor.0 = inertia_tensor.inverse();
             }
+            // Mass is left as set so the body can be made dynamic again
             PhysicsMode::Static => {
-                mass.0 = f32::INFINITY;                
                 inv_mass.0 = 0.;
+                inv_inertia_tensor.0 = Mat3::ZERO;
             }
+            // Kinematic bodies keep their mass but act as infinitely heavy in contacts
+            PhysicsMode::KinematicPosition | PhysicsMode::KinematicVelocity => {
+                inv_mass.0 = 0.;
+                inertia_tensor.0 = inertia;
+                inv_inertia_tensor.0 = Mat3::ZERO;
+            }
+        }
+        if let Some(mut world_inv_inertia_tensor) = world_inv_inertia_tensor {
+            world_inv_inertia_tensor.0 = inv_inertia_tensor.rotated(trans.rotation);
         }
 
-        
-        inv_inertia_tensor.0 = inertia_tensor.inverse();
+        // The body may not be at rest any more
+        if sleeping.is_some() {
+            commands.entity(entity).remove::<Sleeping>();
+        }
     }
 }
 
 pub fn update_aabb(
-    mut query: Query<(&Transform, &mut Aabb, &Handle<Collider>, &Velocity)>,
+    mut query: Query<
+        (&Transform, &mut Aabb, &Handle<Collider>, &Velocity),
+        Or<(Without<Sleeping>, Changed<Handle<Collider>>)>,
+    >,
     colliders: Res<Assets<Collider>>,
     config: Res<PhysicsConfig>,
 ) {
@@ -63,7 +184,76 @@ pub fn update_aabb(
     //let safety_margin_factor_sqr = safety_margin_factor * safety_margin_factor;
 
     for (trans, mut aabb, col, velocity) in query.iter_mut() {
-        let collider = colliders.get(col).unwrap();
+        let Some(collider) = colliders.get(col) else {
+            continue;
+        };
         collider.update_aabb(&mut aabb, trans, velocity, safety_margin_factor);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicsPlugin;
+    use bevy::asset::AssetPlugin;
+
+    #[test]
+    fn test_static_to_dynamic() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Collider>()
+            .insert_resource(PhysicsPlugin::default().config())
+            .add_system(setup_prev_pos)
+            .add_system(setup_mass_and_inertia);
+        let collider = app
+            .world
+            .resource_mut::<Assets<Collider>>()
+            .add(Collider::new_box(1.0, 1.0, 1.0));
+        let entity = app
+            .world
+            .spawn((
+                Transform::IDENTITY,
+                collider,
+                PhysicsMode::Dynamic,
+                Mass(2.0),
+                InverseMass(1.0),
+                InertiaTensor::default(),
+                InverseInertiaTensor::default(),
+                PrevPos::default(),
+                PrevRot::default(),
+            ))
+            .insert((
+                Velocity {
+                    linear: Vec3::X,
+                    angular: Vec3::Y,
+                },
+                PreSolveVelocity {
+                    linear: Vec3::X,
+                    angular: Vec3::Y,
+                },
+            ))
+            .id();
+        app.update();
+        assert_eq!(app.world.get::<InverseMass>(entity).unwrap().0, 0.5);
+
+        // Static bodies can't be moved, but keep the mass they were given
+        app.world.entity_mut(entity).insert(PhysicsMode::Static);
+        app.update();
+        assert_eq!(app.world.get::<Mass>(entity).unwrap().0, 2.0);
+        assert_eq!(app.world.get::<InverseMass>(entity).unwrap().0, 0.0);
+        assert_eq!(app.world.get::<InverseInertiaTensor>(entity).unwrap().0, Mat3::ZERO);
+
+        // A spinning body made static mustn't keep feeding its old velocity into contacts
+        let vel = app.world.get::<Velocity>(entity).unwrap();
+        assert_eq!((vel.linear, vel.angular), (Vec3::ZERO, Vec3::ZERO));
+        let pre_solve_vel = app.world.get::<PreSolveVelocity>(entity).unwrap();
+        assert_eq!((pre_solve_vel.linear, pre_solve_vel.angular), (Vec3::ZERO, Vec3::ZERO));
+
+        app.world.entity_mut(entity).insert(PhysicsMode::Dynamic);
+        app.update();
+        assert_eq!(app.world.get::<Mass>(entity).unwrap().0, 2.0);
+        assert_eq!(app.world.get::<InverseMass>(entity).unwrap().0, 0.5);
+        assert_ne!(app.world.get::<InverseInertiaTensor>(entity).unwrap().0, Mat3::ZERO);
+    }
+}
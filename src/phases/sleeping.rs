@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::{components::*, CollisionPairs, Islands, PhysicsConfig};
+
+// Puts resting islands to sleep and wakes them again, runs on the last substep
+// Islands sleep and wake as a whole so a settled stack doesn't wake itself one body at a time
+pub fn update_sleeping(
+    mut commands: Commands,
+    mut query: Query<(
+        &mut Velocity,
+        &mut PreSolveVelocity,
+        Option<&mut SleepTimer>,
+        Option<&Sleeping>,
+        ChangeTrackers<Transform>,
+        Option<&ExternalForce>,
+        Option<&ExternalTorque>,
+        &PhysicsMode,
+    )>,
+    islands: Res<Islands>,
+    collision_pairs: Res<CollisionPairs>,
+    config: Res<PhysicsConfig>,
+) {
+    if !config.sleeping {
+        return;
+    }
+
+    for island in islands.iter() {
+        // An island is disturbed if an awake body is moving or a sleeping one was moved by the user
+        let mut disturbed = false;
+        let mut min_timer = f32::MAX;
+
+        // Kinematic bodies aren't part of islands, but moving ones disturb what they touch
+        for pair in island.pairs.iter().map(|i| &collision_pairs[*i]) {
+            for entity in [pair.entity_a, pair.entity_b] {
+                if let Ok((vel, .., mode)) = query.get(entity) {
+                    disturbed |= mode.is_kinematic()
+                        && (vel.linear != Vec3::ZERO || vel.angular != Vec3::ZERO);
+                }
+            }
+        }
+
+        for entity in &island.bodies {
+            let Ok((vel, _, timer, sleeping, trans_tracker, force, torque, _)) = query.get(*entity)
+            else {
+                continue;
+            };
+
+            let is_resting = vel.linear.length() < config.sleep_linear_threshold
+                && vel.angular.length() < config.sleep_angular_threshold;
+
+            if sleeping.is_some() {
+                // The engine leaves sleeping bodies alone, so any change here came from the user
+                disturbed |= trans_tracker.is_changed()
+                    || vel.linear != Vec3::ZERO
+                    || vel.angular != Vec3::ZERO
+                    || force.map_or(false, |f| f.force != Vec3::ZERO)
+                    || torque.map_or(false, |t| t.torque != Vec3::ZERO);
+            } else {
+                // Bodies without a timer still count, but never let their island fall asleep
+                disturbed |= !is_resting;
+                min_timer = min_timer.min(timer.map_or(0., |t| t.0));
+            }
+        }
+
+        // Already fully asleep
+        if !disturbed && min_timer == f32::MAX {
+            continue;
+        }
+
+        let fall_asleep = !disturbed && min_timer + config.delta_time >= config.sleep_time;
+        for entity in &island.bodies {
+            let Ok((mut vel, mut pre_solve_vel, mut timer, sleeping, ..)) = query.get_mut(*entity)
+            else {
+                continue;
+            };
+
+            if disturbed {
+                if sleeping.is_some() {
+                    commands.entity(*entity).remove::<Sleeping>();
+                }
+                if let Some(timer) = timer.as_mut() {
+                    timer.0 = 0.;
+                }
+            } else if sleeping.is_none() {
+                if let Some(timer) = timer.as_mut() {
+                    timer.0 += config.delta_time;
+                }
+                if fall_asleep {
+                    commands.entity(*entity).insert(Sleeping);
+                    vel.linear = Vec3::ZERO;
+                    vel.angular = Vec3::ZERO;
+                    pre_solve_vel.linear = Vec3::ZERO;
+                    pre_solve_vel.angular = Vec3::ZERO;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Island, PhysicsPlugin};
+
+    fn spawn_body(world: &mut World, linear: Vec3) -> Entity {
+        world
+            .spawn((
+                Transform::IDENTITY,
+                Velocity {
+                    linear,
+                    angular: Vec3::ZERO,
+                },
+                PreSolveVelocity::default(),
+                SleepTimer::default(),
+                PhysicsMode::Dynamic,
+            ))
+            .id()
+    }
+
+    fn setup(islands: Vec<Vec<Entity>>, world: &mut World) {
+        world.init_resource::<CollisionPairs>();
+        world.insert_resource(Islands(
+            islands
+                .into_iter()
+                .map(|bodies| Island {
+                    bodies,
+                    pairs: Vec::new(),
+                })
+                .collect(),
+        ));
+        world.insert_resource(
+            PhysicsPlugin {
+                sleeping: true,
+                sleep_time: 0.1,
+                ..default()
+            }
+            .config(),
+        );
+    }
+
+    fn run(stage: &mut SystemStage, world: &mut World, steps: usize) {
+        for _ in 0..steps {
+            stage.run(world);
+        }
+    }
+
+    #[test]
+    fn test_sleep_and_wake() {
+        let mut world = World::new();
+        let slow = Vec3::new(0.1, 0.0, 0.0);
+        let resting = [spawn_body(&mut world, slow), spawn_body(&mut world, Vec3::ZERO)];
+        let moving = spawn_body(&mut world, Vec3::X);
+        setup(vec![resting.to_vec(), vec![moving]], &mut world);
+        let mut stage = SystemStage::single(update_sleeping);
+
+        // Slow bodies need sleep_time seconds below the thresholds, fast ones never sleep
+        run(&mut stage, &mut world, 3);
+        assert!(world.get::<Sleeping>(resting[0]).is_none());
+        run(&mut stage, &mut world, 5);
+        assert!(resting.iter().all(|e| world.get::<Sleeping>(*e).is_some()));
+        assert_eq!(world.get::<Velocity>(resting[0]).unwrap().linear, Vec3::ZERO);
+        assert!(world.get::<Sleeping>(moving).is_none());
+
+        // The user giving one body a velocity wakes its whole island
+        world.get_mut::<Velocity>(resting[1]).unwrap().linear = Vec3::X;
+        run(&mut stage, &mut world, 1);
+        assert!(resting.iter().all(|e| world.get::<Sleeping>(*e).is_none()));
+        assert_eq!(world.get::<SleepTimer>(resting[0]).unwrap().0, 0.0);
+
+        // So does moving one
+        world.get_mut::<Velocity>(resting[1]).unwrap().linear = Vec3::ZERO;
+        run(&mut stage, &mut world, 8);
+        assert!(resting.iter().all(|e| world.get::<Sleeping>(*e).is_some()));
+        world.get_mut::<Transform>(resting[0]).unwrap().translation = Vec3::Y;
+        run(&mut stage, &mut world, 1);
+        assert!(resting.iter().all(|e| world.get::<Sleeping>(*e).is_none()));
+    }
+
+    #[test]
+    fn test_body_without_timer_keeps_island_awake() {
+        let mut world = World::new();
+        let resting = spawn_body(&mut world, Vec3::ZERO);
+        let moving = spawn_body(&mut world, Vec3::X);
+        world.entity_mut(moving).remove::<SleepTimer>();
+        setup(vec![vec![resting, moving]], &mut world);
+        let mut stage = SystemStage::single(update_sleeping);
+
+        run(&mut stage, &mut world, 10);
+        assert!(world.get::<Sleeping>(resting).is_none());
+        assert_eq!(world.get::<SleepTimer>(resting).unwrap().0, 0.0);
+    }
+}
@@ -1,87 +1,248 @@
 use bevy::prelude::*;
 
 use crate::{
-    colliders::*, components::*, contacts::*, intersect::*, CollisionPairs, SubstepContacts,
+    colliders::*, components::*, contacts::*, intersect::*, math::QuatExt, CollisionPairs,
+    Islands, PhysicsConfig, SubstepContacts,
 };
 
+use super::is_awake;
+
+/// Mass properties of one side of a contact as the solver sees them
+/// Sleeping and locked degrees of freedom are already zeroed out
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SolverBody {
+    /// Local space offset of the center of mass
+    pub center_of_mass: Vec3,
+    pub inv_mass: Vec3,
+    pub inv_inertia: Mat3,
+}
+
+impl SolverBody {
+    pub fn new(
+        center_of_mass: Option<&CenterOfMass>,
+        inv_mass: &InverseMass,
+        inv_inertia: Mat3,
+        sleeping: Option<&Sleeping>,
+        locked_axes: Option<&LockedAxes>,
+    ) -> Self {
+        // Bodies without a CenterOfMass rotate around their origin
+        let center_of_mass = center_of_mass.map_or(Vec3::ZERO, |c| c.0);
+        // Sleeping bodies act as static until woken
+        if sleeping.is_some() {
+            return Self {
+                center_of_mass,
+                inv_mass: Vec3::ZERO,
+                inv_inertia: Mat3::ZERO,
+            };
+        }
+        let locked_axes = locked_axes.copied().unwrap_or_default();
+        Self {
+            center_of_mass,
+            inv_mass: locked_axes.inverse_mass(inv_mass.0),
+            inv_inertia: locked_axes.inverse_inertia(inv_inertia),
+        }
+    }
+
+    /// Impulses skip bodies that can't move, so static and sleeping bodies aren't flagged as changed
+    pub fn is_movable(&self) -> bool {
+        self.inv_mass != Vec3::ZERO || self.inv_inertia != Mat3::ZERO
+    }
+
+    /// Generalized inverse mass for a unit direction applied at r from the center of mass, eq. 2 and 3
+    pub fn generalized_inverse_mass(&self, r: Vec3, n: Vec3) -> f32 {
+        let rn = r.cross(n);
+        n.dot(self.inv_mass * n) + rn.dot(self.inv_inertia * rn)
+    }
+}
+
 pub fn solve_pos(
-    mut query: Query<(Entity, &mut Transform, &InverseMass, &Handle<Collider>)>,
+    mut query: Query<(
+        Entity,
+        &mut Transform,
+        &InverseMass,
+        Option<&CenterOfMass>,
+        &InverseInertiaTensor,
+        Option<&WorldInverseInertiaTensor>,
+        &Handle<Collider>,
+        &PhysicsMode,
+        Option<&Sensor>,
+        Option<&Sleeping>,
+        Option<&LockedAxes>,
+    ), Without<Quarantined>>,
     collison_pairs: Res<CollisionPairs>,
+    islands: Res<Islands>,
     mut contacts: ResMut<SubstepContacts>,
     colliders: Res<Assets<Collider>>,
+    config: Res<PhysicsConfig>,
 ) {
+    let h_sqr = config.sub_delta_time * config.sub_delta_time;
     contacts.clear();
-    for c in collison_pairs.iter() {
-        let [(entity_a, mut trans_a, inv_mass_a, collider_handle_a), (entity_b, mut trans_b, inv_mass_b, collider_handle_b)] =
-            query.get_many_mut([c.entity_a, c.entity_b]).unwrap();
-
-        let collider_a = colliders.get(collider_handle_a).unwrap();
-        let collider_b = colliders.get(collider_handle_b).unwrap();
-
-        match (collider_a, collider_b) {
-            (Collider::Sphere(sphere_a), Collider::Sphere(sphere_b)) => {
-                if let Some(intersection) = sphere_sphere_intersect(
-                    trans_a.translation,
-                    sphere_a.radius,
-                    trans_b.translation,
-                    sphere_b.radius,
-                ) {
-                    constrain_body_positions(
-                        &mut trans_a,
-                        &mut trans_b,
-                        inv_mass_a,
-                        inv_mass_b,
-                        intersection.normal,
-                        intersection.penetration,
-                    );
-                    contacts.push(Contact {
-                        entity_a,
-                        entity_b,
-                        normal: intersection.normal,
-                        penetration: intersection.penetration,
-                    });
-                }
-            }
-            (_, _) => {
-                if let Some(intersect) =
-                    gjk_intersect(&collider_a, &trans_a, &collider_b, &trans_b, 0.001)
-                {
-                    constrain_body_positions(
-                        &mut trans_a,
-                        &mut trans_b,
-                        inv_mass_a,
-                        inv_mass_b,
-                        intersect.normal,
-                        intersect.penetration,
-                    );
-                    contacts.push(Contact {
-                        entity_a,
-                        entity_b,
-                        normal: intersect.normal,
-                        penetration: intersect.penetration,
-                    });
-                }
+
+    // Work through one island at a time, so the contacts come out grouped by island too
+    // Pairs without a dynamic body belong to no island, they follow at the end
+    let island_pairs = islands
+        .iter()
+        .flat_map(|island| island.pairs.iter().map(|i| (&collison_pairs[*i], true)));
+    let other_pairs = collison_pairs.iter().map(|c| (c, false));
+    for (c, in_island) in island_pairs.chain(other_pairs) {
+        let Ok([(entity_a, mut trans_a, inv_mass_a, com_a, local_inv_inertia_a, world_inv_inertia_a, collider_handle_a, mode_a, sensor_a, sleeping_a, locked_a), (entity_b, mut trans_b, inv_mass_b, com_b, local_inv_inertia_b, world_inv_inertia_b, collider_handle_b, mode_b, sensor_b, sleeping_b, locked_b)]) =
+            query.get_many_mut([c.entity_a, c.entity_b])
+        else {
+            // Quarantined, or reported as MissingComponents by validate_bodies
+            continue;
+        };
+
+        // Already solved with its island
+        if !in_island && (mode_a == &PhysicsMode::Dynamic || mode_b == &PhysicsMode::Dynamic) {
+            continue;
+        }
+
+        // Nothing moves in a pair of resting bodies, keep_resting_contacts carries its contact over
+        if !is_awake(mode_a, sleeping_a) && !is_awake(mode_b, sleeping_b) {
+            continue;
+        }
+
+        let inv_inertia_a = WorldInverseInertiaTensor::get_or_rotate(world_inv_inertia_a, local_inv_inertia_a, trans_a.rotation);
+        let inv_inertia_b = WorldInverseInertiaTensor::get_or_rotate(world_inv_inertia_b, local_inv_inertia_b, trans_b.rotation);
+        let body_a = SolverBody::new(com_a, inv_mass_a, inv_inertia_a, sleeping_a, locked_a);
+        let body_b = SolverBody::new(com_b, inv_mass_b, inv_inertia_b, sleeping_b, locked_b);
+
+        // Sensors only report overlaps
+        let sensor = sensor_a.is_some() || sensor_b.is_some();
+
+        let (Some(collider_a), Some(collider_b)) = (
+            colliders.get(collider_handle_a),
+            colliders.get(collider_handle_b),
+        ) else {
+            continue;
+        };
+
+        let intersection = match (collider_a, collider_b) {
+            (Collider::Sphere(sphere_a), Collider::Sphere(sphere_b)) => sphere_sphere_intersect(
+                trans_a.translation,
+                sphere_a.radius,
+                trans_b.translation,
+                sphere_b.radius,
+            ),
+            (_, _) => gjk_intersect(&collider_a, &trans_a, &collider_b, &trans_b, 0.001),
+        }
+        // The discrete test can miss a pair that passed through each other this substep
+        .or_else(|| {
+            c.speculative
+                .and_then(|speculative| speculative.intersection(&trans_a, &trans_b))
+        });
+
+        if let Some(intersection) = intersection {
+            // Keep the contact points attached to the bodies for the velocity solve
+            let local_point_a =
+                trans_a.rotation.inverse() * (intersection.point_a - trans_a.translation);
+            let local_point_b =
+                trans_b.rotation.inverse() * (intersection.point_b - trans_b.translation);
+
+            let mut lambda = 0.;
+            if !sensor {
+                lambda = constrain_body_positions(
+                    &mut trans_a,
+                    &mut trans_b,
+                    &body_a,
+                    &body_b,
+                    &intersection,
+                );
             }
+            contacts.push(Contact {
+                entity_a,
+                entity_b,
+                normal: intersection.normal,
+                penetration: intersection.penetration,
+                normal_force: -lambda / h_sqr,
+                normal_impulse: 0.,
+                friction_impulse: 0.,
+                sensor,
+                local_point_a,
+                local_point_b,
+            });
         }
     }
 }
 
-/// Solves overlap between two dynamic bodies according to their masses
+/// Solves overlap between two bodies according to their masses and inertia, rotating each
+/// about its center of mass
+/// Returns the lagrange multiplier of the correction
 fn constrain_body_positions(
-    trans_a: &mut Transform,
-    trans_b: &mut Transform,
-    inv_mass_a: &InverseMass,
-    inv_mass_b: &InverseMass,
-    n: Vec3,
-    penetration_depth: f32,
+    trans_a: &mut Mut<Transform>,
+    trans_b: &mut Mut<Transform>,
+    body_a: &SolverBody,
+    body_b: &SolverBody,
+    intersection: &Intersection,
+) -> f32 {
+    let n = intersection.normal;
+    let com_a = trans_a.translation + trans_a.rotation * body_a.center_of_mass;
+    let com_b = trans_b.translation + trans_b.rotation * body_b.center_of_mass;
+    let r_a = intersection.point_a - com_a;
+    let r_b = intersection.point_b - com_b;
+
+    let w_sum = body_a.generalized_inverse_mass(r_a, n) + body_b.generalized_inverse_mass(r_b, n);
+    if w_sum == 0. {
+        // Neither body can move, for example a kinematic body touching a sleeping one
+        return 0.;
+    }
+    let lambda = -intersection.penetration / w_sum;
+    let pos_impulse = n * lambda;
+    apply_position_impulse(trans_a, body_a, com_a, r_a, pos_impulse);
+    apply_position_impulse(trans_b, body_b, com_b, r_b, -pos_impulse);
+    lambda
+}
+
+// Moves the center of mass and rotates around it, eq. 6 to 9
+fn apply_position_impulse(
+    trans: &mut Mut<Transform>,
+    body: &SolverBody,
+    com: Vec3,
+    r: Vec3,
+    impulse: Vec3,
 ) {
-    let w_sum = inv_mass_a.0 + inv_mass_b.0;
-    let pos_impulse = n * (-penetration_depth / w_sum);
-    trans_a.translation += pos_impulse * inv_mass_a.0;
-    trans_b.translation -= pos_impulse * inv_mass_b.0;
+    if !body.is_movable() {
+        return;
+    }
+    let com = com + impulse * body.inv_mass;
+    trans.rotation = trans
+        .rotation
+        .add_rotation(body.inv_inertia * r.cross(impulse));
+    trans.translation = com - trans.rotation * body.center_of_mass;
 }
 
 // Solve a overlap between a dynamic object and a static object
 // fn constrain_body_position(trans: &mut Transform, normal: Vec3, penetration_depth: f32) {
 //     trans.translation -= normal * penetration_depth;
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generalized_inverse_mass() {
+        let body = SolverBody::new(None, &InverseMass(0.5), Mat3::IDENTITY, None, None);
+        assert_eq!(body.center_of_mass, Vec3::ZERO);
+
+        // Pushing through the center of mass only translates, off center it also turns
+        assert_eq!(body.generalized_inverse_mass(Vec3::X, Vec3::X), 0.5);
+        assert_eq!(body.generalized_inverse_mass(Vec3::Y, Vec3::X), 1.5);
+    }
+
+    #[test]
+    fn test_solver_body_sleeping_and_locked() {
+        let com = CenterOfMass(Vec3::Y);
+        let sleeping =
+            SolverBody::new(Some(&com), &InverseMass(0.5), Mat3::IDENTITY, Some(&Sleeping), None);
+        assert_eq!(sleeping.center_of_mass, Vec3::Y);
+        assert!(!sleeping.is_movable());
+        assert_eq!(sleeping.generalized_inverse_mass(Vec3::Y, Vec3::X), 0.0);
+
+        let locked = LockedAxes::new().lock_translation_y().lock_rotation();
+        let body = SolverBody::new(None, &InverseMass(0.5), Mat3::IDENTITY, None, Some(&locked));
+        assert!(body.is_movable());
+        assert_eq!(body.generalized_inverse_mass(Vec3::X, Vec3::Y), 0.0);
+        assert_eq!(body.generalized_inverse_mass(Vec3::Y, Vec3::X), 0.5);
+    }
+}
@@ -1,41 +1,97 @@
 use bevy::prelude::*;
-use crate::{components::*, SubstepContacts};
+use crate::{components::*, PhysicsConfig, SubstepContacts};
+
+use super::SolverBody;
 
 pub fn solve_vel(
     query: Query<(
         &mut Velocity,
         &PreSolveVelocity,
+        &Transform,
         &InverseMass,
+        Option<&CenterOfMass>,
+        &InverseInertiaTensor,
+        Option<&WorldInverseInertiaTensor>,
         &Restitution,
-    )>,
-    contacts: Res<SubstepContacts>,
+        Option<&Friction>,
+        Option<&Sleeping>,
+        Option<&LockedAxes>,
+    ), Without<Quarantined>>,
+    mut contacts: ResMut<SubstepContacts>,
+    config: Res<PhysicsConfig>,
 ) {
-    for c in contacts.iter() {
+    for c in contacts.iter_mut().filter(|c| !c.sensor) {
 
+        // Ensure safety
+        assert!(c.entity_a != c.entity_b);
         let (
-            (mut vel_a, pre_solve_vel_a, inv_mass_a, restitution_a),
-            (mut vel_b, pre_solve_vel_b, inv_mass_b, restitution_b),
-        ) = unsafe {
-            // Ensure safety
-            assert!(c.entity_a != c.entity_b);
-            (
-                query.get_unchecked(c.entity_a).unwrap(),
-                query.get_unchecked(c.entity_b).unwrap(),
-            )
+            Ok((mut vel_a, pre_solve_vel_a, trans_a, inv_mass_a, com_a, local_inv_inertia_a, world_inv_inertia_a, restitution_a, friction_a, sleeping_a, locked_a)),
+            Ok((mut vel_b, pre_solve_vel_b, trans_b, inv_mass_b, com_b, local_inv_inertia_b, world_inv_inertia_b, restitution_b, friction_b, sleeping_b, locked_b)),
+        ) = (
+            unsafe { query.get_unchecked(c.entity_a) },
+            unsafe { query.get_unchecked(c.entity_b) },
+        ) else {
+            continue;
         };
-        
+
+        let inv_inertia_a = WorldInverseInertiaTensor::get_or_rotate(world_inv_inertia_a, local_inv_inertia_a, trans_a.rotation);
+        let inv_inertia_b = WorldInverseInertiaTensor::get_or_rotate(world_inv_inertia_b, local_inv_inertia_b, trans_b.rotation);
+        let body_a = SolverBody::new(com_a, inv_mass_a, inv_inertia_a, sleeping_a, locked_a);
+        let body_b = SolverBody::new(com_b, inv_mass_b, inv_inertia_b, sleeping_b, locked_b);
+
+        // Contact points relative to the centers of mass
+        let r_a = trans_a.rotation * (c.local_point_a - body_a.center_of_mass);
+        let r_b = trans_b.rotation * (c.local_point_b - body_b.center_of_mass);
+
         // Make sure velocities are reflected and restitution/friction calculated
-        let pre_solve_relative_vel = pre_solve_vel_a.linear - pre_solve_vel_b.linear;
+        let pre_solve_relative_vel = (pre_solve_vel_a.linear + pre_solve_vel_a.angular.cross(r_a))
+            - (pre_solve_vel_b.linear + pre_solve_vel_b.angular.cross(r_b));
         let pre_solve_normal_vel = pre_solve_relative_vel.dot(c.normal);
 
-        let relative_vel = vel_a.linear - vel_b.linear;
+        let relative_vel = (vel_a.linear + vel_a.angular.cross(r_a))
+            - (vel_b.linear + vel_b.angular.cross(r_b));
         let normal_vel = relative_vel.dot(c.normal);
-        // averaging restitution
+        let tangent_vel = relative_vel - c.normal * normal_vel;
+        // averaging restitution and friction, bodies without Friction are frictionless
         let restitution = (restitution_a.0 + restitution_b.0) / 2.;
+        let friction = (friction_a.map_or(0., |f| f.0) + friction_b.map_or(0., |f| f.0)) / 2.;
+
+        let w_sum = body_a.generalized_inverse_mass(r_a, c.normal)
+            + body_b.generalized_inverse_mass(r_b, c.normal);
+        if w_sum == 0. {
+            continue;
+        }
 
-        let w_sum = inv_mass_a.0 + inv_mass_b.0;
+        let impulse = (-normal_vel - restitution * pre_solve_normal_vel) / w_sum;
+        // The normal points from a to b, so pushing the bodies apart is a negative impulse on a
+        c.normal_impulse = -impulse;
+
+        apply_velocity_impulse(&mut vel_a, &body_a, r_a, c.normal * impulse);
+        apply_velocity_impulse(&mut vel_b, &body_b, r_b, -c.normal * impulse);
+
+        // Dynamic friction, eq. 30 in the paper, limited by the normal force from the position solve
+        let tangent_speed = tangent_vel.length();
+        if friction > 0. && tangent_speed > f32::EPSILON {
+            let delta_speed = (config.sub_delta_time * friction * c.normal_force).min(tangent_speed);
+            let dir = tangent_vel / tangent_speed;
+            let w_tangent = body_a.generalized_inverse_mass(r_a, dir)
+                + body_b.generalized_inverse_mass(r_b, dir);
+            if w_tangent > 0. {
+                let friction_impulse = delta_speed / w_tangent;
+                c.friction_impulse = friction_impulse;
+
+                apply_velocity_impulse(&mut vel_a, &body_a, r_a, -dir * friction_impulse);
+                apply_velocity_impulse(&mut vel_b, &body_b, r_b, dir * friction_impulse);
+            }
+        }
+    }
+}
 
-        vel_a.linear += c.normal * (-normal_vel - restitution * pre_solve_normal_vel) * inv_mass_a.0 / w_sum;
-        vel_b.linear -= c.normal * (-normal_vel - restitution * pre_solve_normal_vel) * inv_mass_b.0 / w_sum;
+// Applies an impulse at r from the center of mass, eq. 33
+fn apply_velocity_impulse(vel: &mut Mut<Velocity>, body: &SolverBody, r: Vec3, impulse: Vec3) {
+    if !body.is_movable() {
+        return;
     }
+    vel.linear += impulse * body.inv_mass;
+    vel.angular += body.inv_inertia * r.cross(impulse);
 }
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::{colliders::*, components::*, contacts::*, intersect::*, CollisionPairs, PhysicsConfig};
+
+// Closer than this the closest points aren't reliable, the discrete test handles the pair
+const MIN_SEPARATION: f32 = 0.0001;
+
+// Records a contact plane for separated pairs that could close the gap during this step,
+// cheaper than full CCD, and keeps fast bodies from skipping through thin colliders
+pub fn speculative_contacts(
+    mut collision_pairs: ResMut<CollisionPairs>,
+    query: Query<(&Transform, &Velocity, &Handle<Collider>, Option<&Sensor>)>,
+    colliders: Res<Assets<Collider>>,
+    config: Res<PhysicsConfig>,
+) {
+    for pair in collision_pairs.iter_mut() {
+        // Sensors only report real overlaps, like CCD they never get a speculative plane
+        let (
+            Ok((trans_a, vel_a, collider_handle_a, None)),
+            Ok((trans_b, vel_b, collider_handle_b, None)),
+        ) = (query.get(pair.entity_a), query.get(pair.entity_b))
+        else {
+            continue;
+        };
+        let (Some(collider_a), Some(collider_b)) = (
+            colliders.get(collider_handle_a),
+            colliders.get(collider_handle_b),
+        ) else {
+            continue;
+        };
+
+        let Some((point_a, point_b)) = closest_points(collider_a, trans_a, collider_b, trans_b)
+        else {
+            continue;
+        };
+        let delta = point_b - point_a;
+        let distance = delta.length();
+        if distance < MIN_SEPARATION {
+            continue;
+        }
+        let normal = delta / distance;
+
+        // How far the pair could close along the normal this step
+        let closing_speed = (vel_a.linear - vel_b.linear).dot(normal).max(0.0)
+            + collider_a.fastest_linear_speed(vel_a.angular, normal)
+            + collider_b.fastest_linear_speed(vel_b.angular, -normal);
+        if distance > closing_speed * config.delta_time {
+            continue;
+        }
+
+        pair.speculative = Some(SpeculativeContact {
+            normal,
+            local_point_a: trans_a.rotation.inverse() * (point_a - trans_a.translation),
+            local_point_b: trans_b.rotation.inverse() * (point_b - trans_b.translation),
+        });
+    }
+}
+
+// Closest points in world space, None if the colliders already overlap
+fn closest_points(
+    collider_a: &Collider,
+    trans_a: &Transform,
+    collider_b: &Collider,
+    trans_b: &Transform,
+) -> Option<(Vec3, Vec3)> {
+    match (collider_a, collider_b) {
+        (Collider::Sphere(sphere_a), Collider::Sphere(sphere_b)) => {
+            let ab = trans_b.translation - trans_a.translation;
+            let length = ab.length();
+            if length <= sphere_a.radius + sphere_b.radius {
+                return None;
+            }
+            let dir = ab / length;
+            Some((
+                trans_a.translation + dir * sphere_a.radius,
+                trans_b.translation - dir * sphere_b.radius,
+            ))
+        }
+        (_, _) => Some(gjk_closest_points(collider_a, trans_a, collider_b, trans_b)),
+    }
+}
@@ -2,18 +2,55 @@ use bevy::prelude::*;
 use crate::{components::*, PhysicsConfig};
 
 pub fn update_vel(
-    mut query: Query<(&Transform, &PrevPos, &PrevRot, &mut Velocity, &mut PreSolveVelocity)>,
+    mut query: Query<
+        (
+            &Transform,
+            &PrevPos,
+            &PrevRot,
+            &mut Velocity,
+            &mut PreSolveVelocity,
+            Option<&CenterOfMass>,
+            &InverseInertiaTensor,
+            Option<&mut WorldInverseInertiaTensor>,
+            &PhysicsMode,
+        ),
+        (Without<Sleeping>, Without<Quarantined>),
+    >,
     config: Res<PhysicsConfig>,
 ) {
-    for (trans, prev_pos, prev_rot, mut vel, mut pre_solve_vel) in query.iter_mut() {
+    for (
+        trans,
+        prev_pos,
+        prev_rot,
+        mut vel,
+        mut pre_solve_vel,
+        center_of_mass,
+        inv_inertia_tensor,
+        world_inv_inertia_tensor,
+        mode,
+    ) in query.iter_mut()
+    {
 
         // Storing the current velocities
         pre_solve_vel.linear = vel.linear;
         pre_solve_vel.angular = vel.angular;
 
+        // Nothing but the user moves static and kinematic bodies, keep their velocity as is
+        if mode != &PhysicsMode::Dynamic {
+            continue;
+        }
+
+        // The position solve rotated the body, so the velocity solve needs the tensor for the new orientation
+        if let Some(mut world_inv_inertia_tensor) = world_inv_inertia_tensor {
+            world_inv_inertia_tensor.0 = inv_inertia_tensor.rotated(trans.rotation);
+        }
+
 
-        // Updating the linear velocity from the position change
-        vel.linear = (trans.translation - prev_pos.0) / config.sub_delta_time;
+        // Updating the linear velocity from the change in the center of mass position
+        let center_of_mass = center_of_mass.map_or(Vec3::ZERO, |c| c.0);
+        let prev_com = prev_pos.0 + prev_rot.0 * center_of_mass;
+        let com = trans.translation + trans.rotation * center_of_mass;
+        vel.linear = (com - prev_com) / config.sub_delta_time;
 
         // Update the angular velocity based on the orientation difference
         let inv = prev_rot.0.inverse();
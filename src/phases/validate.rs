@@ -0,0 +1,68 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{colliders::*, components::*, diagnostics::*};
+
+type IncompleteBody = (
+    With<Handle<Collider>>,
+    Or<(
+        Or<(
+            Without<Transform>,
+            Without<PhysicsMode>,
+            Without<Mass>,
+            Without<Velocity>,
+            Without<InverseMass>,
+            Without<InertiaTensor>,
+            Without<InverseInertiaTensor>,
+        )>,
+        Or<(
+            Without<Aabb>,
+            Without<PrevPos>,
+            Without<PrevRot>,
+            Without<PreSolveVelocity>,
+            Without<Restitution>,
+        )>,
+    )>,
+);
+
+// Look for bodies the phases can't simulate, warns and sends a PhysicsError the first time
+// each problem is seen, runs on the first substep
+pub fn validate_bodies(
+    bodies: Query<(Entity, &Transform, &Handle<Collider>, &Mass, &PhysicsMode)>,
+    incomplete: Query<Entity, IncompleteBody>,
+    colliders: Res<Assets<Collider>>,
+    mut reported: Local<HashSet<PhysicsError>>,
+    mut errors: EventWriter<PhysicsError>,
+) {
+    let mut current = HashSet::default();
+    for (entity, trans, collider_handle, mass, mode) in bodies.iter() {
+        if !colliders.contains(collider_handle) {
+            current.insert(PhysicsError {
+                entity,
+                kind: PhysicsErrorKind::MissingCollider,
+            });
+        }
+        if mode == &PhysicsMode::Dynamic && !(mass.0 > 0. && mass.0.is_finite()) {
+            current.insert(PhysicsError {
+                entity,
+                kind: PhysicsErrorKind::ZeroMass,
+            });
+        }
+        if !transform_is_finite(trans) {
+            current.insert(PhysicsError {
+                entity,
+                kind: PhysicsErrorKind::NonFiniteTransform,
+            });
+        }
+    }
+    current.extend(incomplete.iter().map(|entity| PhysicsError {
+        entity,
+        kind: PhysicsErrorKind::MissingComponents,
+    }));
+
+    for error in current.iter().filter(|error| !reported.contains(*error)) {
+        warn!("{}", error);
+        errors.send(*error);
+    }
+    // Forget fixed problems so they're reported again if they come back
+    *reported = current;
+}
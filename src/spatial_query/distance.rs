@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+
+use crate::{colliders::*, intersect::*};
+
+#[derive(Debug, Copy, Clone)]
+pub struct ColliderDistance {
+    /// Separation between the colliders, negative penetration depth when overlapping
+    pub distance: f32,
+    /// Closest point on collider a, in world space
+    pub point_a: Vec3,
+    /// Closest point on collider b, in world space
+    pub point_b: Vec3,
+}
+
+/// Exact distance and witness points between two colliders
+pub fn distance(
+    collider_a: &Collider,
+    trans_a: &Transform,
+    collider_b: &Collider,
+    trans_b: &Transform,
+) -> ColliderDistance {
+    if let Some(intersection) = gjk_intersect(collider_a, trans_a, collider_b, trans_b, 0.0) {
+        let point_a = collider_a.get_support(trans_a, intersection.normal, 0.0);
+        return ColliderDistance {
+            distance: -intersection.penetration,
+            point_a,
+            point_b: point_a - intersection.normal * intersection.penetration,
+        };
+    }
+
+    let (point_a, point_b) = gjk_closest_points(collider_a, trans_a, collider_b, trans_b);
+    ColliderDistance {
+        distance: point_a.distance(point_b),
+        point_a,
+        point_b,
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct PointProjection {
+    /// Closest point on the collider's surface, in world space
+    pub point: Vec3,
+    /// The original point was inside the collider
+    pub is_inside: bool,
+}
+
+/// Projects a world space point onto the surface of the collider
+pub fn project_point(collider: &Collider, trans: &Transform, point: Vec3) -> PointProjection {
+    let local_point = trans.rotation.inverse() * (point - trans.translation);
+    PointProjection {
+        point: trans.translation + trans.rotation * collider.project_point(local_point),
+        is_inside: collider.contains_point(local_point),
+    }
+}
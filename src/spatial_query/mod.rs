@@ -0,0 +1,119 @@
+mod distance;
+mod overlap;
+mod ray;
+mod shape;
+
+pub use distance::*;
+pub use overlap::*;
+pub use ray::*;
+pub use shape::*;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashSet};
+
+use crate::{colliders::*, components::*};
+
+/// Controls which bodies a spatial query considers
+#[derive(Debug, Clone)]
+pub struct QueryFilter {
+    pub excluded_entities: HashSet<Entity>,
+    pub exclude_sensors: bool,
+    /// Only bodies on at least one of these [`CollisionLayers`] are considered
+    pub layers: u32,
+}
+
+impl Default for QueryFilter {
+    fn default() -> Self {
+        Self {
+            excluded_entities: HashSet::default(),
+            exclude_sensors: false,
+            layers: CollisionLayers::ALL,
+        }
+    }
+}
+
+impl QueryFilter {
+    pub fn exclude_entity(mut self, entity: Entity) -> Self {
+        self.excluded_entities.insert(entity);
+        self
+    }
+
+    pub fn exclude_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+
+    pub fn with_layers(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    fn allows(
+        &self,
+        entity: Entity,
+        sensor: Option<&Sensor>,
+        layers: Option<&CollisionLayers>,
+    ) -> bool {
+        if self.exclude_sensors && sensor.is_some() {
+            return false;
+        }
+        if layers.map_or(CollisionLayers::ALL, |l| l.memberships) & self.layers == 0 {
+            return false;
+        }
+        !self.excluded_entities.contains(&entity)
+    }
+}
+
+/// System param for querying the physics world, all results are in world space
+/// Queries visit every body and reject most by their Aabb before the exact test, so their cost
+/// grows linearly with the number of bodies
+#[derive(SystemParam)]
+pub struct SpatialQuery<'w, 's> {
+    colliders: Res<'w, Assets<Collider>>,
+    bodies: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Transform,
+            &'static Aabb,
+            &'static Handle<Collider>,
+            Option<&'static Sensor>,
+            Option<&'static CollisionLayers>,
+        ),
+    >,
+}
+
+// Radius of a sphere around the collider's origin that contains it at any rotation
+fn bounding_radius(collider: &Collider) -> f32 {
+    let aabb = collider.get_aabb();
+    aabb.mins.length().max(aabb.maxs.length())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use bevy::{asset::AssetPlugin, ecs::system::SystemState};
+
+    // An app with the given colliders spawned as bodies, their Aabbs already computed
+    pub(crate) fn spawn_bodies(bodies: Vec<(Collider, Transform)>) -> (App, Vec<Entity>) {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Collider>();
+
+        let entities = bodies
+            .into_iter()
+            .map(|(collider, trans)| {
+                let mut aabb = Aabb::default();
+                collider.update_aabb(&mut aabb, &trans, &Velocity::default(), 0.0);
+                let handle = app.world.resource_mut::<Assets<Collider>>().add(collider);
+                app.world.spawn((trans, aabb, handle)).id()
+            })
+            .collect();
+        (app, entities)
+    }
+
+    pub(crate) fn spatial_query(app: &mut App) -> SystemState<SpatialQuery<'static, 'static>> {
+        SystemState::new(&mut app.world)
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::{colliders::*, components::Aabb, intersect::*};
+
+use super::{bounding_radius, QueryFilter, SpatialQuery};
+
+impl<'w, 's> SpatialQuery<'w, 's> {
+    /// Returns every body whose collider contains the point
+    pub fn point_intersections(&self, point: Vec3, filter: &QueryFilter) -> Vec<Entity> {
+        let point_aabb = Aabb::new(point, point);
+        self.bodies
+            .iter()
+            .filter(|(entity, _, aabb, _, sensor, layers)| {
+                filter.allows(*entity, *sensor, *layers) && point_aabb.intersection(aabb)
+            })
+            .filter_map(|(entity, trans, _, collider_handle, ..)| {
+                let collider = self.colliders.get(collider_handle)?;
+                let local_point = trans.rotation.inverse() * (point - trans.translation);
+                collider.contains_point(local_point).then_some(entity)
+            })
+            .collect()
+    }
+
+    /// Returns every body whose broad phase AABB intersects the given AABB
+    pub fn aabb_intersections(&self, aabb: &Aabb, filter: &QueryFilter) -> Vec<Entity> {
+        self.bodies
+            .iter()
+            .filter(|(entity, _, body_aabb, _, sensor, layers)| {
+                filter.allows(*entity, *sensor, *layers) && aabb.intersection(body_aabb)
+            })
+            .map(|(entity, ..)| entity)
+            .collect()
+    }
+
+    /// Returns every body whose collider overlaps the collider placed at the transform
+    pub fn shape_intersections(
+        &self,
+        collider: &Collider,
+        trans: &Transform,
+        filter: &QueryFilter,
+    ) -> Vec<Entity> {
+        let radius = bounding_radius(collider);
+        let shape_aabb = Aabb::new(
+            trans.translation - Vec3::splat(radius),
+            trans.translation + Vec3::splat(radius),
+        );
+
+        self.bodies
+            .iter()
+            .filter(|(entity, _, aabb, _, sensor, layers)| {
+                filter.allows(*entity, *sensor, *layers) && shape_aabb.intersection(aabb)
+            })
+            .filter_map(|(entity, other_trans, _, collider_handle, ..)| {
+                let other = self.colliders.get(collider_handle)?;
+                let overlaps = match (collider, other) {
+                    (Collider::Sphere(sphere_a), Collider::Sphere(sphere_b)) => {
+                        sphere_sphere_intersect(
+                            trans.translation,
+                            sphere_a.radius,
+                            other_trans.translation,
+                            sphere_b.radius,
+                        )
+                        .is_some()
+                    }
+                    (_, _) => gjk_intersect(collider, trans, other, other_trans, 0.0).is_some(),
+                };
+                overlaps.then_some(entity)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::CollisionLayers, spatial_query::tests::*};
+
+    #[test]
+    fn test_overlap_queries() {
+        let (mut app, entities) = spawn_bodies(vec![
+            (Collider::new_sphere(1.0), Transform::IDENTITY),
+            (Collider::new_box(2.0, 2.0, 2.0), Transform::from_xyz(5.0, 0.0, 0.0)),
+        ]);
+        app.world
+            .entity_mut(entities[1])
+            .insert(CollisionLayers::new(0b10, CollisionLayers::ALL));
+        let mut state = spatial_query(&mut app);
+        let query = state.get_mut(&mut app.world);
+        let filter = QueryFilter::default();
+
+        assert_eq!(query.point_intersections(Vec3::new(0.5, 0.0, 0.0), &filter), vec![entities[0]]);
+        assert_eq!(query.point_intersections(Vec3::new(5.5, 0.0, 0.0), &filter), vec![entities[1]]);
+        assert!(query.point_intersections(Vec3::new(3.0, 0.0, 0.0), &filter).is_empty());
+
+        let aabb = Aabb::new(Vec3::splat(-2.0), Vec3::new(6.0, 2.0, 2.0));
+        let mut hits = query.aabb_intersections(&aabb, &filter);
+        hits.sort();
+        assert_eq!(hits, entities);
+
+        // Bodies without CollisionLayers are on every layer
+        let first_layer = QueryFilter::default().with_layers(0b01);
+        assert_eq!(query.aabb_intersections(&aabb, &first_layer), vec![entities[0]]);
+
+        let sphere = Collider::new_sphere(0.5);
+        let at = |x| Transform::from_xyz(x, 0.0, 0.0);
+        assert_eq!(query.shape_intersections(&sphere, &at(1.2), &filter), vec![entities[0]]);
+        assert!(query.shape_intersections(&sphere, &at(3.3), &filter).is_empty());
+        assert_eq!(query.shape_intersections(&sphere, &at(4.2), &filter), vec![entities[1]]);
+        assert!(query
+            .shape_intersections(&sphere, &at(4.2), &first_layer)
+            .is_empty());
+    }
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::colliders::*;
+
+use super::{QueryFilter, SpatialQuery};
+
+#[derive(Debug, Copy, Clone)]
+pub struct RayHit {
+    pub entity: Entity,
+    /// Distance along the ray
+    pub distance: f32,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// See [`RayIntersection::feature`]
+    pub feature: u32,
+}
+
+impl<'w, 's> SpatialQuery<'w, 's> {
+    /// Returns the closest hit along the ray within max_toi
+    pub fn cast_ray(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_toi: f32,
+        filter: &QueryFilter,
+    ) -> Option<RayHit> {
+        let mut closest: Option<RayHit> = None;
+        self.for_each_ray_hit(origin, direction, max_toi, filter, |hit| {
+            if closest.map_or(true, |c| hit.distance < c.distance) {
+                closest = Some(hit);
+            }
+        });
+        closest
+    }
+
+    /// Returns every hit along the ray within max_toi, sorted by distance
+    pub fn cast_ray_all(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_toi: f32,
+        filter: &QueryFilter,
+    ) -> Vec<RayHit> {
+        let mut hits = Vec::new();
+        self.for_each_ray_hit(origin, direction, max_toi, filter, |hit| hits.push(hit));
+        hits.sort_unstable_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    fn for_each_ray_hit(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_toi: f32,
+        filter: &QueryFilter,
+        mut f: impl FnMut(RayHit),
+    ) {
+        let direction = direction.normalize();
+
+        for (entity, trans, aabb, collider_handle, sensor, layers) in self.bodies.iter() {
+            if !filter.allows(entity, sensor, layers) {
+                continue;
+            }
+
+            // Use the body's Aabb to skip the narrow test
+            match aabb.ray_intersection(origin, direction) {
+                Some(t) if t <= max_toi => {}
+                _ => continue,
+            }
+
+            let Some(collider) = self.colliders.get(collider_handle) else {
+                continue;
+            };
+
+            // Convert the ray to object space
+            let inv_rot = trans.rotation.inverse();
+            let mut ray = Ray {
+                origin: inv_rot * (origin - trans.translation),
+                direction: inv_rot * direction,
+            };
+
+            if let Some(intersection) = collider.intersect(&mut ray) {
+                if intersection.toi > max_toi {
+                    continue;
+                }
+
+                f(RayHit {
+                    entity,
+                    distance: intersection.toi,
+                    point: origin + direction * intersection.toi,
+                    normal: trans.rotation * intersection.normal,
+                    feature: intersection.feature,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial_query::tests::*;
+
+    #[test]
+    fn test_cast_ray() {
+        let (mut app, entities) = spawn_bodies(vec![
+            (Collider::new_box(2.0, 2.0, 2.0), Transform::from_xyz(5.0, 0.0, 0.0)),
+            (Collider::new_sphere(1.0), Transform::from_xyz(10.0, 0.0, 0.0)),
+        ]);
+        let mut state = spatial_query(&mut app);
+        let query = state.get_mut(&mut app.world);
+        let filter = QueryFilter::default();
+
+        let hit = query.cast_ray(Vec3::ZERO, Vec3::X, 100.0, &filter).unwrap();
+        assert_eq!(hit.entity, entities[0]);
+        assert_eq!(hit.distance, 4.0);
+        assert_eq!(hit.point, Vec3::new(4.0, 0.0, 0.0));
+        assert_eq!(hit.normal, -Vec3::X);
+
+        let hits = query.cast_ray_all(Vec3::ZERO, Vec3::X, 100.0, &filter);
+        assert_eq!(hits.iter().map(|h| h.entity).collect::<Vec<_>>(), entities);
+        assert!((hits[1].distance - 9.0).abs() < 1e-5);
+
+        // Too short, or pointing away
+        assert!(query.cast_ray(Vec3::ZERO, Vec3::X, 3.0, &filter).is_none());
+        assert!(query.cast_ray(Vec3::ZERO, -Vec3::X, 100.0, &filter).is_none());
+    }
+}
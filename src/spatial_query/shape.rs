@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+
+use crate::{colliders::*, components::Aabb, intersect::*};
+
+use super::{bounding_radius, QueryFilter, SpatialQuery};
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShapeHit {
+    pub entity: Entity,
+    /// Distance travelled along the cast direction
+    pub toi: f32,
+    /// Contact point on the hit body
+    pub point: Vec3,
+    /// Surface normal of the hit body at the contact point
+    pub normal: Vec3,
+}
+
+impl<'w, 's> SpatialQuery<'w, 's> {
+    /// Sweeps the collider from start along direction, returns the first body it would hit
+    /// The collider does not rotate during the sweep
+    pub fn cast_shape(
+        &self,
+        collider: &Collider,
+        start: &Transform,
+        direction: Vec3,
+        max_distance: f32,
+        filter: &QueryFilter,
+    ) -> Option<ShapeHit> {
+        let direction = direction.normalize();
+
+        // Bound the whole sweep so most bodies are rejected by their Aabb alone
+        let radius = bounding_radius(collider);
+        let end = start.translation + direction * max_distance;
+        let sweep_aabb = Aabb::new(
+            start.translation.min(end) - Vec3::splat(radius),
+            start.translation.max(end) + Vec3::splat(radius),
+        );
+
+        let mut closest: Option<ShapeHit> = None;
+        for (entity, trans, aabb, collider_handle, sensor, layers) in self.bodies.iter() {
+            if !filter.allows(entity, sensor, layers) || !sweep_aabb.intersection(aabb) {
+                continue;
+            }
+
+            let Some(other) = self.colliders.get(collider_handle) else {
+                continue;
+            };
+
+            let max_toi = closest.map_or(max_distance, |c| c.toi);
+            if let Some(hit) = time_of_impact(collider, start, direction, max_toi, other, trans) {
+                closest = Some(ShapeHit {
+                    entity,
+                    toi: hit.toi,
+                    point: hit.point,
+                    normal: hit.normal,
+                });
+            }
+        }
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial_query::tests::*;
+
+    #[test]
+    fn test_cast_shape() {
+        let (mut app, entities) = spawn_bodies(vec![
+            (Collider::new_box(2.0, 2.0, 2.0), Transform::IDENTITY),
+            (Collider::new_box(2.0, 2.0, 2.0), Transform::from_xyz(4.0, 0.0, 0.0)),
+        ]);
+        let mut state = spatial_query(&mut app);
+        let query = state.get_mut(&mut app.world);
+
+        let sphere = Collider::new_sphere(0.5);
+        let start = Transform::from_xyz(-5.0, 0.0, 0.0);
+        let hit = query
+            .cast_shape(&sphere, &start, Vec3::X, 10.0, &QueryFilter::default())
+            .unwrap();
+        assert_eq!(hit.entity, entities[0]);
+        assert!((hit.toi - 3.5).abs() < 0.01);
+
+        // Excluding the nearer box finds the one behind it
+        let filter = QueryFilter::default().exclude_entity(entities[0]);
+        let hit = query.cast_shape(&sphere, &start, Vec3::X, 10.0, &filter).unwrap();
+        assert_eq!(hit.entity, entities[1]);
+        assert!((hit.toi - 7.5).abs() < 0.01);
+
+        assert!(query
+            .cast_shape(&sphere, &start, Vec3::Y, 10.0, &QueryFilter::default())
+            .is_none());
+    }
+}